@@ -0,0 +1,104 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// Directory of plain SQL migrations
+const SQL_DIR: &str = "src/migrator/sql";
+
+/// Embed plain SQL migrations named "YYYYMMDD_HHMMSS_name.up.sql" and optional "YYYYMMDD_HHMMSS_name.down.sql" into
+/// `$OUT_DIR/sql_migrations.rs` as list of (name, up, down), included by `src/migrator/mod.rs`.
+/// Commit the crate is built from is set as GIT_SHA ENV, recorded in migration history
+fn main() {
+  let dir = Path::new(SQL_DIR);
+  // Missing path is always reported as changed, rebuilding the crate on every build
+  if dir.exists() {
+    println!("cargo:rerun-if-changed={}", SQL_DIR);
+  } else {
+    println!("cargo:rerun-if-changed=build.rs");
+  }
+  println!("cargo:rustc-env=GIT_SHA={}", git_sha());
+
+  let mut names: Vec<String> = Vec::new();
+  if let Ok(entries) = fs::read_dir(dir) {
+    for entry in entries.flatten() {
+      let file_name = entry.file_name().to_string_lossy().to_string();
+      if !file_name.ends_with(".sql") {
+        continue;
+      }
+      let name = match file_name.strip_suffix(".up.sql").or_else(|| file_name.strip_suffix(".down.sql")) {
+        Some(name) => name.to_string(),
+        None => panic!("SQL migration {} must end with .up.sql or .down.sql", file_name),
+      };
+      if !is_timestamped(&name) {
+        panic!(
+          "SQL migration {} must be named YYYYMMDD_HHMMSS_name like Rust migrations, so it runs in order of its timestamp",
+          file_name
+        );
+      }
+      if !dir.join(format!("{}.up.sql", name)).exists() {
+        panic!("SQL migration {} has no {}.up.sql", file_name, name);
+      }
+      if !names.contains(&name) {
+        names.push(name);
+      }
+    }
+  }
+  names.sort();
+
+  let mut code = String::from("&[\n");
+  for name in names {
+    let up = dir.join(format!("{}.up.sql", name)).canonicalize().unwrap();
+    let down = dir.join(format!("{}.down.sql", name));
+    let down = match down.exists() {
+      true => format!("Some(include_str!({:?}))", down.canonicalize().unwrap()),
+      false => String::from("None"),
+    };
+    code.push_str(&format!("  ({:?}, include_str!({:?}), {}),\n", name, up, down));
+  }
+  code.push(']');
+
+  fs::write(Path::new(&env::var("OUT_DIR").unwrap()).join("sql_migrations.rs"), code).unwrap();
+}
+
+/// Returns true if name starts with date and time parts of migration timestamp, e.g. "20220603_120000_add_rating"
+fn is_timestamped(name: &str) -> bool {
+  let parts: Vec<&str> = name.splitn(3, '_').collect();
+  parts.len() == 3
+    && parts[0].len() == 8
+    && parts[1].len() == 6
+    && parts[..2].iter().all(|part| part.chars().all(|c| c.is_ascii_digit()))
+    && !parts[2].is_empty()
+}
+
+/// Short hash of HEAD commit, "unknown" outside of git repository. Build is rerun once HEAD moves. Only existing files
+/// are watched, as missing one reruns the build every time. Branch packed by git is watched through packed-refs
+fn git_sha() -> String {
+  rerun_if_exists(".git/HEAD");
+  if let Some(reference) =
+    fs::read_to_string(".git/HEAD").ok().and_then(|head| head.strip_prefix("ref: ").map(String::from))
+  {
+    let reference = format!(".git/{}", reference.trim());
+    if !rerun_if_exists(&reference) {
+      rerun_if_exists(".git/packed-refs");
+    }
+  }
+
+  Command::new("git")
+    .args(["rev-parse", "--short", "HEAD"])
+    .output()
+    .ok()
+    .filter(|output| output.status.success())
+    .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    .filter(|sha| !sha.is_empty())
+    .unwrap_or_else(|| String::from("unknown"))
+}
+
+/// Rerun build once file changes. Returns false if file does not exist
+fn rerun_if_exists(path: &str) -> bool {
+  let exists = Path::new(path).exists();
+  if exists {
+    println!("cargo:rerun-if-changed={}", path);
+  }
+  exists
+}
@@ -0,0 +1,21 @@
+use std::io::Error;
+
+use dotenvy::dotenv;
+use env_logger::init;
+use futures::executor::block_on;
+
+use rust_orm_template::connector::db::{Db, DbConfig};
+
+async fn run() -> Result<(), Error> {
+  let db = Db::bootstrap(DbConfig::from_env()?).await?;
+  db.close().await.map_err(|e| Error::other(e.to_string()))
+}
+
+/// Create DB, schema, extensions and runtime role if absent
+fn main() {
+  dotenv().unwrap();
+  init();
+  if let Err(err) = block_on(run()) {
+    panic!("{}", err);
+  }
+}
@@ -1,5 +1,5 @@
 use std::env;
-use std::fs::remove_dir_all;
+use std::fs::{create_dir_all, read_to_string, remove_dir_all, write};
 use std::io::{Error, ErrorKind};
 
 use dotenvy::dotenv;
@@ -9,15 +9,18 @@ use sea_orm_cli::cli::GenerateSubcommands;
 use sea_orm_cli::commands::run_generate_command;
 use sea_orm_cli::DateTimeCrate;
 
+#[cfg(feature = "entities")]
 use rust_orm_template::connector::db::Db;
+use rust_orm_template::connector::db::{DbConfig, BOOKKEEPING_TABLES};
 
 enum OperationType {
   Generate,
   Erase,
+  Check,
 }
 
 async fn generate_entities(entities_dir: String) -> Result<(), Error> {
-  let db = Db::new().await?;
+  let config = DbConfig::from_env()?;
 
   if let Err(e) = run_generate_command(
     GenerateSubcommands::Entity {
@@ -25,11 +28,11 @@ async fn generate_entities(entities_dir: String) -> Result<(), Error> {
       expanded_format: true,
       include_hidden_tables: true,
       tables: Vec::new(),
-      ignore_tables: vec![String::from("seaql_migrations")],
+      ignore_tables: BOOKKEEPING_TABLES.iter().map(|table| table.to_string()).collect(),
       max_connections: 10,
-      output_dir: entities_dir,
-      database_schema: db.db_schema,
-      database_url: db.db_url,
+      output_dir: entities_dir.clone(),
+      database_schema: config.db_schema,
+      database_url: config.db_url,
       with_serde: String::from("none"),
       serde_skip_deserializing_primary_key: false,
       serde_skip_hidden_column: false,
@@ -46,14 +49,66 @@ async fn generate_entities(entities_dir: String) -> Result<(), Error> {
     return Err(Error::new(ErrorKind::Interrupted, e.to_string()));
   }
 
-  Ok(())
+  register_schemas(&entities_dir)
+}
+
+/// Append `all_schemas()` listing every generated entity to entities module, so drift check covers new tables
+fn register_schemas(entities_dir: &str) -> Result<(), Error> {
+  let path = format!("{}/mod.rs", entities_dir);
+  let source = read_to_string(&path)?;
+  write(&path, format!("{}{}", source, schemas_source(&entity_modules(&source))))
+}
+
+/// Entity modules declared by generated module, prelude is left out
+fn entity_modules(source: &str) -> Vec<String> {
+  source
+    .lines()
+    .filter_map(|line| line.trim().strip_prefix("pub mod ")?.strip_suffix(';'))
+    .filter(|module| *module != "prelude")
+    .map(String::from)
+    .collect()
 }
 
+/// Source of `all_schemas()` of entities module
+fn schemas_source(modules: &[String]) -> String {
+  let schemas: Vec<String> = modules.iter().map(|module| format!("TableSchema::of({}::Entity)", module)).collect();
+  format!(
+    "\nuse crate::connector::db::TableSchema;\n\n/// Schemas of every entity, compared with DB by drift check. \
+     Appended by `entities generate`\npub fn all_schemas() -> Vec<TableSchema> {{\n  vec![{}]\n}}\n",
+    schemas.join(", ")
+  )
+}
+
+/// Remove generated entities, leaving empty module. Until entities are generated again, code using them is left out
+/// by building without "entities" feature, e.g. `cargo run --no-default-features --bin entities generate`
 async fn erase_entities(entities_dir: String) -> Result<(), Error> {
-  remove_dir_all(entities_dir)?;
+  remove_dir_all(&entities_dir)?;
+  create_dir_all(&entities_dir)?;
+  write(format!("{}/mod.rs", entities_dir), "")?;
   Ok(())
 }
 
+/// Fail with list of differences if generated entities do not match DB schema
+#[cfg(feature = "entities")]
+async fn check_entities() -> Result<(), Error> {
+  let db = Db::new().await?;
+  let result = db.check_drift().await;
+  db.close().await.map_err(|e| Error::other(e.to_string()))?;
+
+  let drift = result.map_err(|e| Error::other(e.to_string()))?;
+  if drift.is_empty() {
+    println!("Entities match DB schema");
+    return Ok(());
+  }
+  let lines: Vec<String> = drift.iter().map(|drift| drift.to_string()).collect();
+  Err(Error::other(format!("Entities drifted from DB schema, run entities generate:\n{}", lines.join("\n"))))
+}
+
+#[cfg(not(feature = "entities"))]
+async fn check_entities() -> Result<(), Error> {
+  Err(Error::new(ErrorKind::Unsupported, "Entities check needs \"entities\" feature"))
+}
+
 fn get_operation_type(args: Vec<String>) -> Result<OperationType, Error> {
   if args.len() < 2 {
     return Err(Error::new(ErrorKind::InvalidInput, "Operation type must be provided as an argument"));
@@ -64,6 +119,7 @@ fn get_operation_type(args: Vec<String>) -> Result<OperationType, Error> {
   match operation.to_lowercase().as_str() {
     "generate" => Ok(OperationType::Generate),
     "erase" => Ok(OperationType::Erase),
+    "check" => Ok(OperationType::Check),
     opt => Err(Error::new(ErrorKind::InvalidInput, format!("Unsupported operation {}", opt))),
   }
 }
@@ -77,5 +133,25 @@ fn main() {
   match get_operation_type(env::args().collect()).unwrap() {
     OperationType::Generate => block_on(generate_entities(entities_dir)).unwrap(),
     OperationType::Erase => block_on(erase_entities(entities_dir)).unwrap(),
+    OperationType::Check => {
+      if let Err(err) = block_on(check_entities()) {
+        panic!("{}", err);
+      }
+    },
+  }
+}
+
+#[cfg(test)]
+mod register_schemas_test {
+  use super::{entity_modules, schemas_source};
+
+  #[test]
+  fn schemas_of_generated_modules() {
+    let modules = entity_modules("//! `SeaORM` Entity\n\npub mod prelude;\n\npub mod bakery;\npub mod chef;\n");
+
+    assert_eq!(modules, vec!["bakery", "chef"]);
+    assert!(schemas_source(&modules).contains(
+      "pub fn all_schemas() -> Vec<TableSchema> {\n  vec![TableSchema::of(bakery::Entity), TableSchema::of(chef::Entity)]\n}\n"
+    ));
   }
 }
@@ -1,29 +1,173 @@
 use std::env;
+use std::fs;
 use std::io::{Error, ErrorKind};
 
 use dotenvy::dotenv;
 use env_logger::init;
 use futures::executor::block_on;
 
-use rust_orm_template::connector::db::{Db, Migration, MigrationType};
+use rust_orm_template::connector::db::{
+  changes_table, history_json, history_text, lint_text, plan_sql, report_json, report_text, snapshot_diff, status_json,
+  status_table, Db, Migration, MigrationType, SNAPSHOT_FILE,
+};
+use rust_orm_template::shutdown::Shutdown;
 
-async fn run(migration_type: MigrationType) -> Result<(), Error> {
-  let db = Db::new().await?;
-  let migration = Migration::new(migration_type);
+/// Options given as flags. Flags start with "--" and may be placed anywhere after the binary name,
+/// e.g. "migration status --json" or "migration up --dry-run --output plan.sql".
+/// Target migration given by "--to" is part of migration type, see `get_migration_type`.
+/// After the operation "--snapshot" writes schema to `SNAPSHOT_FILE`, "--check-snapshot" fails if the file is stale
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Options {
+  json: bool,
+  force: bool,
+  dry_run: bool,
+  output: Option<String>,
+  snapshot: bool,
+  check_snapshot: bool,
+}
 
-  if let Err(e) = db.migrate(migration).await {
-    Err(Error::new(ErrorKind::Other, e.to_string()))
-  } else {
-    Ok(())
+/// Flags followed by a value
+const VALUE_FLAGS: [&str; 2] = ["--output", "--to"];
+
+async fn run(migration_type: MigrationType, shutdown: Shutdown, options: Options) -> Result<(), Error> {
+  let db = Db::new_migration().await?;
+
+  let mut risky = 0;
+  let result =
+    match migration_type {
+      MigrationType::Lint => db.migration_lint().await.map(|warnings| {
+        risky = warnings.len();
+        if options.json {
+          serde_json::to_string_pretty(&warnings).unwrap_or_default() + "\n"
+        } else {
+          lint_text(&warnings)
+        }
+      }),
+      MigrationType::Changes => db.change_states().await.map(|states| {
+        if options.json {
+          serde_json::to_string_pretty(&states).unwrap_or_default() + "\n"
+        } else {
+          changes_table(&states)
+        }
+      }),
+      MigrationType::History(limit) => db.migration_history(limit).await.map(|runs| {
+        if options.json {
+          history_json(&runs) + "\n"
+        } else {
+          history_text(&runs)
+        }
+      }),
+      MigrationType::Status => {
+        db.migration_status().await.map(
+          |states| {
+            if options.json {
+              status_json(&states) + "\n"
+            } else {
+              status_table(&states)
+            }
+          },
+        )
+      },
+      migration_type if options.dry_run => db.migration_plan(&Migration::new(migration_type)).await.map(|plans| {
+        if options.json {
+          serde_json::to_string_pretty(&plans).unwrap_or_default() + "\n"
+        } else {
+          plan_sql(&plans)
+        }
+      }),
+      migration_type => db
+        .migrate(Migration::new(migration_type).with_shutdown(shutdown).with_force(options.force))
+        .await
+        .map(|report| if options.json { report_json(&report) + "\n" } else { report_text(&report) }),
+    };
+  let snapshot = match result {
+    Ok(_) if options.snapshot || options.check_snapshot => db.schema_snapshot().await.map(Some),
+    _ => Ok(None),
+  };
+  db.close().await.map_err(|e| Error::other(e.to_string()))?;
+
+  let output = result.map_err(|e| Error::other(e.to_string()))?;
+  if let Some(snapshot) = snapshot.map_err(|e| Error::other(e.to_string()))? {
+    if options.check_snapshot {
+      check_snapshot(&fs::read_to_string(SNAPSHOT_FILE).unwrap_or_default(), &snapshot)?;
+    }
+    if options.snapshot {
+      fs::write(SNAPSHOT_FILE, snapshot)?;
+    }
+  }
+  match options.output {
+    Some(path) => fs::write(path, output)?,
+    None => print!("{}", output),
   }
+  if risky > 0 {
+    return Err(Error::other(format!("Pending migrations have {} risky operations", risky)));
+  }
+  Ok(())
+}
+
+/// Fail with diff if committed snapshot differs from the live schema
+fn check_snapshot(committed: &str, snapshot: &str) -> Result<(), Error> {
+  let diff = snapshot_diff(committed, snapshot);
+  if diff.is_empty() {
+    return Ok(());
+  }
+  Err(Error::other(format!("{} is stale, regenerate it with --snapshot:\n{}", SNAPSHOT_FILE, diff.join("\n"))))
+}
+
+fn get_options(args: &[String]) -> Result<Options, Error> {
+  let mut options = Options::default();
+  let mut args = args.iter().skip(1);
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "--json" => options.json = true,
+      "--force" => options.force = true,
+      "--dry-run" => options.dry_run = true,
+      "--snapshot" => options.snapshot = true,
+      "--check-snapshot" => options.check_snapshot = true,
+      "--output" => match args.next() {
+        Some(path) => options.output = Some(path.clone()),
+        None => return Err(Error::new(ErrorKind::InvalidInput, "--output must be followed by a file path")),
+      },
+      "--to" => match args.next() {
+        Some(_) => {},
+        None => return Err(Error::new(ErrorKind::InvalidInput, "--to must be followed by a migration name")),
+      },
+      flag if flag.starts_with("--") => {
+        return Err(Error::new(ErrorKind::InvalidInput, format!("Unsupported flag {}", flag)))
+      },
+      _ => {},
+    }
+  }
+  Ok(options)
 }
 
 fn get_migration_type(args: Vec<String>) -> Result<MigrationType, Error> {
+  let mut positional: Vec<String> = Vec::new();
+  let mut target: Option<String> = None;
+  let mut args = args.into_iter();
+  while let Some(arg) = args.next() {
+    if arg == "--to" {
+      target = args.next();
+    } else if VALUE_FLAGS.contains(&arg.as_str()) {
+      args.next();
+    } else if !arg.starts_with("--") {
+      positional.push(arg);
+    }
+  }
+
+  let args = positional;
   if args.len() < 2 {
     return Err(Error::new(ErrorKind::InvalidInput, "Migration type must be provided as an argument"));
   }
 
   let operation = &args[1];
+  if let Some(target) = target {
+    if args.len() > 2 {
+      return Err(Error::new(ErrorKind::InvalidInput, "steps cannot be combined with --to"));
+    }
+    return MigrationType::from_target(operation, &target);
+  }
+
   let steps: Option<u32> = if args.len() < 3 {
     None
   } else {
@@ -34,8 +178,8 @@ fn get_migration_type(args: Vec<String>) -> Result<MigrationType, Error> {
   };
 
   match MigrationType::from_string(operation, steps) {
-    Ok(r) => return Ok(r),
-    Err(_) => return Err(Error::new(ErrorKind::InvalidData, "Unsupported migration type")),
+    Ok(r) => Ok(r),
+    Err(_) => Err(Error::new(ErrorKind::InvalidData, "Unsupported migration type")),
   }
 }
 
@@ -43,15 +187,18 @@ fn get_migration_type(args: Vec<String>) -> Result<MigrationType, Error> {
 fn main() {
   dotenv().unwrap();
   init();
-  let migration_type = get_migration_type(env::args().collect()).unwrap();
-  if let Err(err) = block_on(run(migration_type)) {
+  let shutdown = Shutdown::install().unwrap();
+  let args: Vec<String> = env::args().collect();
+  let options = get_options(&args).unwrap();
+  let migration_type = get_migration_type(args).unwrap();
+  if let Err(err) = block_on(run(migration_type, shutdown, options)) {
     panic!("{}", err);
   }
 }
 
 #[cfg(test)]
 mod get_migration_type_test {
-  use super::get_migration_type;
+  use super::{check_snapshot, get_migration_type, get_options, Options};
   use rust_orm_template::connector::db::MigrationType;
   use std::io::ErrorKind;
 
@@ -62,7 +209,7 @@ mod get_migration_type_test {
         assert_eq!(e.kind(), ErrorKind::InvalidInput);
         assert_eq!(e.to_string(), "Migration type must be provided as an argument");
       },
-      Ok(_) => assert!(false),
+      Ok(_) => panic!(),
     }
   }
 
@@ -73,7 +220,7 @@ mod get_migration_type_test {
         assert_eq!(e.kind(), ErrorKind::InvalidData);
         assert_eq!(e.to_string(), "Unsupported migration type");
       },
-      Ok(_) => assert!(false),
+      Ok(_) => panic!(),
     }
   }
 
@@ -81,7 +228,7 @@ mod get_migration_type_test {
   fn with_refresh_type() {
     match get_migration_type(vec!["migration".to_string(), "refresh".to_string()]) {
       Ok(r) => assert_eq!(r, MigrationType::Refresh),
-      Err(_) => assert!(false),
+      Err(_) => panic!(),
     }
   }
 
@@ -89,7 +236,7 @@ mod get_migration_type_test {
   fn with_refresh_type_and_correct_steps() {
     match get_migration_type(vec!["migration".to_string(), "refresh".to_string(), "2".to_string()]) {
       Ok(r) => assert_eq!(r, MigrationType::Refresh),
-      Err(_) => assert!(false),
+      Err(_) => panic!(),
     }
   }
 
@@ -100,7 +247,7 @@ mod get_migration_type_test {
         assert_eq!(e.kind(), ErrorKind::InvalidData);
         assert_eq!(e.to_string(), "steps must be a number");
       },
-      Ok(_) => assert!(false),
+      Ok(_) => panic!(),
     }
   }
 
@@ -108,7 +255,7 @@ mod get_migration_type_test {
   fn with_up_type() {
     match get_migration_type(vec!["migration".to_string(), "up".to_string()]) {
       Ok(r) => assert_eq!(r, MigrationType::Up(None)),
-      Err(_) => assert!(false),
+      Err(_) => panic!(),
     }
   }
 
@@ -116,7 +263,7 @@ mod get_migration_type_test {
   fn with_up_type_and_correct_steps() {
     match get_migration_type(vec!["migration".to_string(), "up".to_string(), "2".to_string()]) {
       Ok(r) => assert_eq!(r, MigrationType::Up(Some(2))),
-      Err(_) => assert!(false),
+      Err(_) => panic!(),
     }
   }
 
@@ -127,7 +274,7 @@ mod get_migration_type_test {
         assert_eq!(e.kind(), ErrorKind::InvalidData);
         assert_eq!(e.to_string(), "steps must be a number");
       },
-      Ok(_) => assert!(false),
+      Ok(_) => panic!(),
     }
   }
 
@@ -135,7 +282,7 @@ mod get_migration_type_test {
   fn with_down_type() {
     match get_migration_type(vec!["migration".to_string(), "down".to_string()]) {
       Ok(r) => assert_eq!(r, MigrationType::Down(None)),
-      Err(_) => assert!(false),
+      Err(_) => panic!(),
     }
   }
 
@@ -143,7 +290,7 @@ mod get_migration_type_test {
   fn with_down_type_and_correct_steps() {
     match get_migration_type(vec!["migration".to_string(), "down".to_string(), "2".to_string()]) {
       Ok(r) => assert_eq!(r, MigrationType::Down(Some(2))),
-      Err(_) => assert!(false),
+      Err(_) => panic!(),
     }
   }
 
@@ -154,7 +301,7 @@ mod get_migration_type_test {
         assert_eq!(e.kind(), ErrorKind::InvalidData);
         assert_eq!(e.to_string(), "steps must be a number");
       },
-      Ok(_) => assert!(false),
+      Ok(_) => panic!(),
     }
   }
 
@@ -165,7 +312,7 @@ mod get_migration_type_test {
         assert_eq!(e.kind(), ErrorKind::InvalidData);
         assert_eq!(e.to_string(), "steps must be a number");
       },
-      Ok(_) => assert!(false),
+      Ok(_) => panic!(),
     }
   }
 
@@ -176,7 +323,195 @@ mod get_migration_type_test {
         assert_eq!(e.kind(), ErrorKind::InvalidData);
         assert_eq!(e.to_string(), "steps must be a number");
       },
-      Ok(_) => assert!(false),
+      Ok(_) => panic!(),
+    }
+  }
+
+  #[test]
+  fn with_status_type() {
+    match get_migration_type(vec!["migration".to_string(), "status".to_string()]) {
+      Ok(r) => assert_eq!(r, MigrationType::Status),
+      Err(_) => panic!(),
+    }
+  }
+
+  #[test]
+  fn with_status_type_and_json_flag() {
+    let args = vec!["migration".to_string(), "status".to_string(), "--json".to_string()];
+    assert!(get_options(&args).unwrap().json);
+    match get_migration_type(args) {
+      Ok(r) => assert_eq!(r, MigrationType::Status),
+      Err(_) => panic!(),
+    }
+  }
+
+  #[test]
+  fn without_flags() {
+    assert_eq!(get_options(&["migration".to_string(), "status".to_string()]).unwrap(), Options::default());
+  }
+
+  #[test]
+  fn with_unsupported_flag() {
+    match get_options(&["migration".to_string(), "up".to_string(), "--foo".to_string()]) {
+      Err(e) => {
+        assert_eq!(e.kind(), ErrorKind::InvalidInput);
+        assert_eq!(e.to_string(), "Unsupported flag --foo");
+      },
+      Ok(_) => panic!(),
+    }
+  }
+
+  #[test]
+  fn with_dry_run_and_output() {
+    let args: Vec<String> =
+      ["migration", "up", "--dry-run", "--output", "plan.sql", "2"].iter().map(|arg| arg.to_string()).collect();
+
+    let options = get_options(&args).unwrap();
+    assert!(options.dry_run);
+    assert_eq!(options.output, Some(String::from("plan.sql")));
+    match get_migration_type(args) {
+      Ok(r) => assert_eq!(r, MigrationType::Up(Some(2))),
+      Err(_) => panic!(),
+    }
+  }
+
+  #[test]
+  fn with_output_without_path() {
+    match get_options(&["migration".to_string(), "up".to_string(), "--output".to_string()]) {
+      Err(e) => assert_eq!(e.kind(), ErrorKind::InvalidInput),
+      Ok(_) => panic!(),
+    }
+  }
+
+  #[test]
+  fn with_fresh_type_and_force_flag() {
+    let args = vec!["migration".to_string(), "fresh".to_string(), "--force".to_string()];
+    assert!(get_options(&args).unwrap().force);
+    match get_migration_type(args) {
+      Ok(r) => assert_eq!(r, MigrationType::Fresh),
+      Err(_) => panic!(),
+    }
+  }
+
+  #[test]
+  fn with_repair_type() {
+    match get_migration_type(vec!["migration".to_string(), "repair".to_string()]) {
+      Ok(r) => assert_eq!(r, MigrationType::Repair),
+      Err(_) => panic!(),
+    }
+  }
+
+  #[test]
+  fn with_lint_type() {
+    match get_migration_type(vec!["migration".to_string(), "lint".to_string(), "--json".to_string()]) {
+      Ok(r) => assert_eq!(r, MigrationType::Lint),
+      Err(_) => panic!(),
+    }
+  }
+
+  #[test]
+  fn with_history_type_and_limit() {
+    match get_migration_type(vec!["migration".to_string(), "history".to_string(), "10".to_string()]) {
+      Ok(r) => assert_eq!(r, MigrationType::History(Some(10))),
+      Err(_) => panic!(),
+    }
+  }
+
+  #[test]
+  fn with_changes_type() {
+    match get_migration_type(vec!["migration".to_string(), "changes".to_string()]) {
+      Ok(r) => assert_eq!(r, MigrationType::Changes),
+      Err(_) => panic!(),
+    }
+  }
+
+  #[test]
+  fn with_verify_type() {
+    match get_migration_type(vec!["migration".to_string(), "verify".to_string(), "--force".to_string()]) {
+      Err(e) => assert_eq!(e.to_string(), "Unsupported migration type"),
+      Ok(_) => panic!(),
+    }
+  }
+
+  #[test]
+  fn with_reset_type() {
+    match get_migration_type(vec!["migration".to_string(), "reset".to_string()]) {
+      Ok(r) => assert_eq!(r, MigrationType::Reset),
+      Err(_) => panic!(),
+    }
+  }
+
+  #[test]
+  fn with_down_type_and_target() {
+    let args: Vec<String> = ["migration", "down", "--to", "m_20220602_000001_create_bakery_table"]
+      .iter()
+      .map(|arg| arg.to_string())
+      .collect();
+
+    assert_eq!(get_options(&args).unwrap(), Options::default());
+    match get_migration_type(args) {
+      Ok(r) => assert_eq!(r, MigrationType::DownTo(String::from("m_20220602_000001_create_bakery_table"))),
+      Err(_) => panic!(),
+    }
+  }
+
+  #[test]
+  fn with_unknown_target() {
+    let args: Vec<String> = ["migration", "up", "--to", "m_foo"].iter().map(|arg| arg.to_string()).collect();
+
+    match get_migration_type(args) {
+      Err(e) => {
+        assert_eq!(e.kind(), ErrorKind::NotFound);
+        assert_eq!(e.to_string(), "Unknown target migration m_foo");
+      },
+      Ok(_) => panic!(),
+    }
+  }
+
+  #[test]
+  fn with_target_and_steps() {
+    let args: Vec<String> = ["migration", "up", "2", "--to", "m_20220602_000001_create_bakery_table"]
+      .iter()
+      .map(|arg| arg.to_string())
+      .collect();
+
+    match get_migration_type(args) {
+      Err(e) => assert_eq!(e.kind(), ErrorKind::InvalidInput),
+      Ok(_) => panic!(),
+    }
+  }
+
+  #[test]
+  fn with_target_without_name() {
+    match get_options(&["migration".to_string(), "down".to_string(), "--to".to_string()]) {
+      Err(e) => assert_eq!(e.kind(), ErrorKind::InvalidInput),
+      Ok(_) => panic!(),
+    }
+  }
+
+  #[test]
+  fn with_snapshot_flags() {
+    let args: Vec<String> =
+      ["migration", "up", "--snapshot", "--check-snapshot"].iter().map(|arg| arg.to_string()).collect();
+
+    let options = get_options(&args).unwrap();
+    assert!(options.snapshot && options.check_snapshot);
+    match get_migration_type(args) {
+      Ok(r) => assert_eq!(r, MigrationType::Up(None)),
+      Err(_) => panic!(),
+    }
+  }
+
+  #[test]
+  fn with_stale_snapshot() {
+    let snapshot = "table chef\n  column id integer NOT NULL\n";
+
+    assert!(check_snapshot(snapshot, snapshot).is_ok());
+    match check_snapshot("", snapshot) {
+      Err(e) => assert!(e
+        .to_string()
+        .ends_with("stale, regenerate it with --snapshot:\n table chef\n+  column id integer NOT NULL")),
+      Ok(_) => panic!(),
     }
   }
 }
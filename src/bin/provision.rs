@@ -0,0 +1,24 @@
+use std::io::Error;
+
+use dotenvy::dotenv;
+use env_logger::init;
+use futures::executor::block_on;
+
+use rust_orm_template::connector::db::Db;
+
+async fn run() -> Result<(), Error> {
+  let db = Db::new_migration().await?;
+
+  let result = db.provision().await.map(|_| ());
+  db.close().await.map_err(|e| Error::other(e.to_string()))?;
+  result.map_err(|e| Error::other(e.to_string()))
+}
+
+/// Create runtime role and grant it DML on the migrated schema
+fn main() {
+  dotenv().unwrap();
+  init();
+  if let Err(err) = block_on(run()) {
+    panic!("{}", err);
+  }
+}
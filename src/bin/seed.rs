@@ -0,0 +1,106 @@
+use std::env;
+use std::io::{Error, ErrorKind};
+
+use dotenvy::dotenv;
+use env_logger::init;
+use futures::executor::block_on;
+
+use rust_orm_template::connector::db::{seed_table, Db, Seed};
+use rust_orm_template::fixture::Fixtures;
+use rust_orm_template::shutdown::Shutdown;
+use sea_orm::DbErr;
+
+#[derive(Debug, PartialEq, Eq)]
+enum OperationType {
+  Run,
+  Status,
+  Load(String),
+}
+
+async fn run(operation: OperationType, shutdown: Shutdown) -> Result<(), Error> {
+  let db = Db::new_migration().await?;
+  let seed = Seed::new().with_shutdown(shutdown);
+
+  let result = match operation {
+    OperationType::Run => db.seed(seed).await.map(|ran| {
+      if ran.is_empty() {
+        String::from("Nothing to seed\n")
+      } else {
+        ran.iter().map(|name| format!("{}\n", name)).collect()
+      }
+    }),
+    OperationType::Status => db.seed_status(&seed).await.map(|states| seed_table(&states)),
+    OperationType::Load(path) => match Fixtures::from_file(&path) {
+      Ok(fixtures) => db.load_fixtures(&fixtures).await.map(|_| format!("Loaded {}\n", path)),
+      Err(e) => Err(DbErr::Custom(e.to_string())),
+    },
+  };
+  db.close().await.map_err(|e| Error::other(e.to_string()))?;
+
+  print!("{}", result.map_err(|e| Error::other(e.to_string()))?);
+  Ok(())
+}
+
+fn get_operation_type(args: Vec<String>) -> Result<OperationType, Error> {
+  match args.get(1).map(|operation| operation.to_lowercase()).as_deref() {
+    None | Some("run") => Ok(OperationType::Run),
+    Some("status") => Ok(OperationType::Status),
+    Some("load") => match args.get(2) {
+      Some(path) => Ok(OperationType::Load(path.clone())),
+      None => Err(Error::new(ErrorKind::InvalidInput, "Fixture file must be provided after load")),
+    },
+    Some(opt) => Err(Error::new(ErrorKind::InvalidInput, format!("Unsupported operation {}", opt))),
+  }
+}
+
+/// Run seeders of APP_PROFILE which did not run yet, or load fixture file given after "load"
+fn main() {
+  dotenv().unwrap();
+  init();
+  let shutdown = Shutdown::install().unwrap();
+  let operation = get_operation_type(env::args().collect()).unwrap();
+  if let Err(err) = block_on(run(operation, shutdown)) {
+    panic!("{}", err);
+  }
+}
+
+#[cfg(test)]
+mod get_operation_type_test {
+  use super::{get_operation_type, OperationType};
+  use std::io::ErrorKind;
+
+  #[test]
+  fn without_args() {
+    assert_eq!(get_operation_type(vec!["seed".to_string()]).unwrap(), OperationType::Run);
+  }
+
+  #[test]
+  fn with_status() {
+    assert_eq!(get_operation_type(vec!["seed".to_string(), "Status".to_string()]).unwrap(), OperationType::Status);
+  }
+
+  #[test]
+  fn with_load() {
+    let args = vec!["seed".to_string(), "load".to_string(), "fixtures/demo.yaml".to_string()];
+    assert_eq!(get_operation_type(args).unwrap(), OperationType::Load(String::from("fixtures/demo.yaml")));
+  }
+
+  #[test]
+  fn with_load_without_file() {
+    match get_operation_type(vec!["seed".to_string(), "load".to_string()]) {
+      Err(e) => assert_eq!(e.kind(), ErrorKind::InvalidInput),
+      Ok(_) => panic!(),
+    }
+  }
+
+  #[test]
+  fn with_invalid_operation() {
+    match get_operation_type(vec!["seed".to_string(), "foo".to_string()]) {
+      Err(e) => {
+        assert_eq!(e.kind(), ErrorKind::InvalidInput);
+        assert_eq!(e.to_string(), "Unsupported operation foo");
+      },
+      Ok(_) => panic!(),
+    }
+  }
+}
@@ -0,0 +1,15 @@
+use chrono::Utc;
+use dotenvy::dotenv;
+use env_logger::init;
+use futures::executor::block_on;
+
+use rust_orm_template::connector::db::Baseline;
+
+/// Squash every migration of `src/migrator` into single baseline migration
+fn main() {
+  dotenv().unwrap();
+  init();
+  let baseline = block_on(Baseline::record(Utc::now())).unwrap();
+  baseline.write("src/migrator").unwrap();
+  println!("Squashed {} into {}", baseline.squashed.join(", "), baseline.name);
+}
@@ -0,0 +1,277 @@
+use std::time::Duration;
+
+use async_std::task::sleep;
+use log::info;
+use sea_orm::{ConnectionTrait, DatabaseBackend, DatabaseConnection, DbErr, Statement, TransactionTrait};
+use serde::Serialize;
+
+use super::provision::{quote_ident, quote_literal};
+use crate::shutdown::Shutdown;
+
+/// Table holding progress of every backfill, see `Backfill`
+pub const BACKFILL_TABLE: &str = "seaql_backfill_progress";
+
+/// Outcome of `Backfill::run`. Rows and batches are counted for this run only, last key is the last key done
+/// by any run. Backfill which is not done was interrupted and resumes after last key on the next run
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct BackfillReport {
+  pub rows: u64,
+  pub batches: u64,
+  pub last_key: Option<i64>,
+  pub done: bool,
+}
+
+/// Uses to update rows of large table in batches ordered by integer key, each committed with its progress,
+/// so interrupted backfill resumes after the last batch done. Initialize by running:
+/// ```
+/// use std::time::Duration;
+/// use rust_orm_template::connector::db::Backfill;
+///
+/// let backfill = Backfill::new("chef_contact", "chef", "contact = contact_details::jsonb")
+///   .with_filter("contact IS NULL")
+///   .with_batch_size(500)
+///   .with_pause(Duration::from_millis(100));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Backfill {
+  name: String,
+  table: String,
+  assignment: String,
+  key: String,
+  filter: Option<String>,
+  batch_size: u64,
+  pause: Duration,
+  shutdown: Option<Shutdown>,
+}
+
+impl Backfill {
+  /// Uses to initialize backfill of table by SET clause, e.g. "contact = contact_details::jsonb".
+  /// Name identifies progress of the backfill. Rows are ordered by "id" in batches of 1000 without pause
+  pub fn new(name: &str, table: &str, assignment: &str) -> Backfill {
+    Backfill {
+      name: name.to_string(),
+      table: table.to_string(),
+      assignment: assignment.to_string(),
+      key: String::from("id"),
+      filter: None,
+      batch_size: 1000,
+      pause: Duration::ZERO,
+      shutdown: None,
+    }
+  }
+
+  /// Uses to override integer key column rows are ordered by
+  pub fn with_key(mut self, key: &str) -> Backfill {
+    self.key = key.to_string();
+    self
+  }
+
+  /// Uses to update only rows matching SQL condition, e.g. "contact IS NULL"
+  pub fn with_filter(mut self, filter: &str) -> Backfill {
+    self.filter = Some(filter.to_string());
+    self
+  }
+
+  /// Uses to override number of rows updated by single batch
+  pub fn with_batch_size(mut self, batch_size: u64) -> Backfill {
+    self.batch_size = batch_size.max(1);
+    self
+  }
+
+  /// Uses to pause between batches, leaving room for other load of DB
+  pub fn with_pause(mut self, pause: Duration) -> Backfill {
+    self.pause = pause;
+    self
+  }
+
+  /// Uses to stop backfill between batches once shutdown is requested. Batch being run is never interrupted
+  pub fn with_shutdown(mut self, shutdown: Shutdown) -> Backfill {
+    self.shutdown = Some(shutdown);
+    self
+  }
+
+  /// Run batches from the last key done until no rows are left. Each batch is committed with progress, so it takes
+  /// connection, not transaction: from migration it runs only with `TransactionMode::None`, see `ColumnChange`
+  pub async fn run(&self, connection: &DatabaseConnection) -> Result<BackfillReport, DbErr> {
+    connection.execute_unprepared(&progress_table_sql()).await?;
+    let mut report = BackfillReport::default();
+    let mut total = 0;
+    if let Some(row) = connection
+      .query_one(Statement::from_string(
+        DatabaseBackend::Postgres,
+        format!("SELECT last_key, rows, done FROM {} WHERE name = {}", BACKFILL_TABLE, quote_literal(&self.name)),
+      ))
+      .await?
+    {
+      report.last_key = row.try_get("", "last_key")?;
+      total = row.try_get::<i64>("", "rows")?;
+      report.done = row.try_get("", "done")?;
+    }
+    if report.done {
+      info!("Backfill '{}' is already done, {} rows updated", self.name, total);
+      return Ok(report);
+    }
+    if report.last_key.is_some() {
+      info!("Backfill '{}' resumes after key {}", self.name, report.last_key.unwrap_or_default());
+    }
+
+    loop {
+      if self.shutdown.as_ref().is_some_and(Shutdown::is_requested) {
+        info!("Backfill '{}' is interrupted by shutdown request after key {:?}", self.name, report.last_key);
+        break;
+      }
+      if report.batches > 0 && !self.pause.is_zero() {
+        sleep(self.pause).await;
+      }
+
+      let transaction = connection.begin().await?;
+      let (rows, last_key) = match transaction.query_one(self.batch_statement(report.last_key)).await? {
+        Some(row) => (row.try_get::<i64>("", "updated_rows")?, row.try_get::<Option<i64>>("", "last_key")?),
+        None => (0, None),
+      };
+      report.rows += rows as u64;
+      report.batches += 1;
+      report.last_key = last_key.or(report.last_key);
+      report.done = (rows as u64) < self.batch_size;
+      total += rows;
+      transaction.execute(self.progress_statement(report.last_key, total, report.done)).await?;
+      transaction.commit().await?;
+
+      info!(
+        "Backfill '{}': batch {} updated {} rows, {} in total, last key {:?}",
+        self.name, report.batches, rows, total, report.last_key
+      );
+      if report.done {
+        info!("Backfill '{}' is done, {} rows updated", self.name, total);
+        break;
+      }
+    }
+    Ok(report)
+  }
+
+  /// Forget progress, so the next run starts from the first key
+  pub fn reset_statements(&self) -> Vec<String> {
+    vec![progress_table_sql(), format!("DELETE FROM {} WHERE name = {}", BACKFILL_TABLE, quote_literal(&self.name))]
+  }
+
+  /// Update of the next batch after last key, returning number of updated rows and the last key of them
+  fn batch_statement(&self, last_key: Option<i64>) -> Statement {
+    let table = quote_ident(&self.table);
+    let key = quote_ident(&self.key);
+    let mut conditions: Vec<String> = Vec::new();
+    if let Some(last_key) = last_key {
+      conditions.push(format!("{} > {}", key, last_key));
+    }
+    if let Some(filter) = &self.filter {
+      conditions.push(format!("({})", filter));
+    }
+    let condition = match conditions.is_empty() {
+      true => String::new(),
+      false => format!(" WHERE {}", conditions.join(" AND ")),
+    };
+
+    Statement::from_string(
+      DatabaseBackend::Postgres,
+      format!(
+        "WITH batch AS (SELECT {key} AS batch_key FROM {table}{condition} ORDER BY {key} LIMIT {limit} FOR UPDATE), \
+         updated AS (UPDATE {table} SET {assignment} FROM batch WHERE {table}.{key} = batch.batch_key \
+         RETURNING batch.batch_key) \
+         SELECT count(*) AS updated_rows, max(batch_key)::bigint AS last_key FROM updated",
+        key = key,
+        table = table,
+        condition = condition,
+        limit = self.batch_size,
+        assignment = self.assignment
+      ),
+    )
+  }
+
+  fn progress_statement(&self, last_key: Option<i64>, rows: i64, done: bool) -> Statement {
+    Statement::from_sql_and_values(
+      DatabaseBackend::Postgres,
+      &format!(
+        "INSERT INTO {} (name, last_key, rows, done, updated_at) VALUES ($1, $2, $3, $4, extract(epoch FROM now())::bigint) \
+         ON CONFLICT (name) DO UPDATE SET last_key = EXCLUDED.last_key, rows = EXCLUDED.rows, done = EXCLUDED.done, \
+         updated_at = EXCLUDED.updated_at",
+        BACKFILL_TABLE
+      ),
+      [self.name.clone().into(), last_key.into(), rows.into(), done.into()],
+    )
+  }
+}
+
+fn progress_table_sql() -> String {
+  format!(
+    "CREATE TABLE IF NOT EXISTS {} (name varchar PRIMARY KEY, last_key bigint, rows bigint NOT NULL, \
+     done boolean NOT NULL, updated_at bigint NOT NULL)",
+    BACKFILL_TABLE
+  )
+}
+
+#[cfg(test)]
+mod backfill_test {
+  use super::Backfill;
+  use crate::shutdown::Shutdown;
+  use futures::executor::block_on;
+  use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult, Value};
+  use std::collections::BTreeMap;
+
+  fn batch(rows: i64, last_key: Option<i64>) -> Vec<BTreeMap<&'static str, Value>> {
+    vec![BTreeMap::from([("updated_rows", Value::BigInt(Some(rows))), ("last_key", Value::BigInt(last_key))])]
+  }
+
+  #[test]
+  fn batch_statement() {
+    let backfill = Backfill::new("chef_contact", "chef", "contact = contact_details::jsonb")
+      .with_filter("contact IS NULL")
+      .with_batch_size(2);
+
+    let sql = backfill.batch_statement(Some(10)).to_string();
+
+    assert!(sql.starts_with(
+      "WITH batch AS (SELECT \"id\" AS batch_key FROM \"chef\" WHERE \"id\" > 10 AND (contact IS NULL) ORDER BY \
+       \"id\" LIMIT 2 FOR UPDATE)"
+    ));
+    assert!(sql.contains("UPDATE \"chef\" SET contact = contact_details::jsonb FROM batch"));
+    assert!(!backfill.batch_statement(None).to_string().contains("WHERE \"id\" >"));
+  }
+
+  #[test]
+  fn run_resumes_after_last_key() {
+    let connection = MockDatabase::new(DatabaseBackend::Postgres)
+      .append_exec_results(vec![MockExecResult::default(); 3])
+      .append_query_results([vec![BTreeMap::from([
+        ("last_key", Value::BigInt(Some(2))),
+        ("rows", Value::BigInt(Some(2))),
+        ("done", Value::Bool(Some(false))),
+      ])]])
+      .append_query_results([batch(2, Some(4)), batch(1, Some(5))])
+      .into_connection();
+
+    let report =
+      block_on(Backfill::new("chef_contact", "chef", "name = upper(name)").with_batch_size(2).run(&connection))
+        .unwrap();
+
+    assert_eq!((report.rows, report.batches, report.last_key, report.done), (3, 2, Some(5), true));
+    let log = format!("{:?}", connection.into_transaction_log());
+    assert!(log.contains("\\\"id\\\" > 2 ORDER BY"));
+    assert!(log.contains("\\\"id\\\" > 4 ORDER BY"));
+    assert!(log.contains("BigInt(Some(5)), BigInt(Some(5)), Bool(Some(true))"));
+  }
+
+  #[test]
+  fn run_stops_on_shutdown() {
+    let connection = MockDatabase::new(DatabaseBackend::Postgres)
+      .append_exec_results([MockExecResult::default()])
+      .append_query_results([Vec::<BTreeMap<&str, Value>>::new()])
+      .into_connection();
+    let shutdown = Shutdown::new();
+    shutdown.request();
+
+    let backfill =
+      Backfill::new("chef_contact", "chef", "name = upper(name)").with_batch_size(2).with_shutdown(shutdown);
+    let report = block_on(backfill.run(&connection)).unwrap();
+
+    assert_eq!((report.rows, report.batches, report.last_key, report.done), (0, 0, None, false));
+  }
+}
@@ -0,0 +1,104 @@
+use sea_orm::{ConnectionTrait, DatabaseBackend, DatabaseConnection, DbErr, Statement};
+
+use super::provision::{quote_ident, quote_literal};
+use super::DbConfig;
+
+/// Uses to prepare fresh environment before migrations: creates DB, schema and extensions if absent.
+/// All steps are idempotent, so bootstrap may be run on every deploy. Initialize by running:
+/// ```
+/// use rust_orm_template::connector::db::{Bootstrap, DbConfig};
+///
+/// if let Ok(config) = DbConfig::from_env() {
+///   let bootstrap = Bootstrap::new(&config);
+/// }
+/// ```
+#[derive(Debug, PartialEq, Eq)]
+pub struct Bootstrap {
+  database: String,
+  schema: String,
+  extensions: Vec<String>,
+}
+
+impl Bootstrap {
+  /// Uses to initialize bootstrap of DB described in config
+  #[allow(dead_code)]
+  pub fn new(config: &DbConfig) -> Bootstrap {
+    Bootstrap {
+      database: config.db_name.clone(),
+      schema: config.db_schema.clone(),
+      extensions: config.db_extensions.clone(),
+    }
+  }
+
+  /// Statement creating DB. Postgres has no "IF NOT EXISTS" for databases, so existence is checked beforehand
+  pub fn database_statement(&self) -> String {
+    format!("CREATE DATABASE {}", quote_ident(&self.database))
+  }
+
+  /// Statements creating schema and extensions inside DB
+  pub fn schema_statements(&self) -> Vec<String> {
+    let mut statements = vec![format!("CREATE SCHEMA IF NOT EXISTS {}", quote_ident(&self.schema))];
+    for extension in &self.extensions {
+      statements.push(format!(
+        "CREATE EXTENSION IF NOT EXISTS {} WITH SCHEMA {}",
+        quote_ident(extension),
+        quote_ident(&self.schema)
+      ));
+    }
+    statements
+  }
+
+  /// Create DB if absent. Must be run on maintenance DB
+  #[allow(dead_code)]
+  pub async fn create_database(&self, maintenance: &DatabaseConnection) -> Result<&Self, DbErr> {
+    let exists = maintenance
+      .query_one(Statement::from_string(
+        DatabaseBackend::Postgres,
+        format!("SELECT 1 FROM pg_catalog.pg_database WHERE datname = {}", quote_literal(&self.database)),
+      ))
+      .await?
+      .is_some();
+
+    if !exists {
+      maintenance.execute_unprepared(&self.database_statement()).await?;
+    }
+
+    Ok(self)
+  }
+
+  /// Create schema and extensions if absent. Must be run on bootstrapped DB
+  #[allow(dead_code)]
+  pub async fn create_schema(&self, connection: &DatabaseConnection) -> Result<&Self, DbErr> {
+    for statement in self.schema_statements() {
+      connection.execute_unprepared(&statement).await?;
+    }
+
+    Ok(self)
+  }
+}
+
+#[cfg(test)]
+mod bootstrap_test {
+  use super::Bootstrap;
+
+  fn bootstrap(extensions: Vec<String>) -> Bootstrap {
+    Bootstrap { database: String::from("test"), schema: String::from("schema"), extensions }
+  }
+
+  #[test]
+  fn database_statement() {
+    assert_eq!(bootstrap(Vec::new()).database_statement(), "CREATE DATABASE \"test\"");
+  }
+
+  #[test]
+  fn schema_statements_without_extensions() {
+    assert_eq!(bootstrap(Vec::new()).schema_statements(), vec![String::from("CREATE SCHEMA IF NOT EXISTS \"schema\"")]);
+  }
+
+  #[test]
+  fn schema_statements_with_extensions() {
+    let statements = bootstrap(vec![String::from("pgcrypto")]).schema_statements();
+    assert_eq!(statements.len(), 2);
+    assert_eq!(statements[1], "CREATE EXTENSION IF NOT EXISTS \"pgcrypto\" WITH SCHEMA \"schema\"");
+  }
+}
@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+
+use log::warn;
+use sea_orm::{ConnectionTrait, DatabaseBackend, DatabaseConnection, DbErr, Statement};
+use sea_orm_migration::{MigrationTrait, MigratorTrait, SchemaManager};
+use sha2::{Digest, Sha256};
+
+use super::dry_run::record_migration;
+use super::MigrationDirection;
+use crate::migrator::Migrator;
+
+/// Table holding checksum of every applied migration. Kept apart from seaql_migrations, which is owned by SeaORM
+pub const CHECKSUM_TABLE: &str = "seaql_migration_checksums";
+
+/// SHA-256 of SQL generated by migration's up, see `record_migration`
+pub async fn migration_checksum(migration: &dyn MigrationTrait) -> Result<String, DbErr> {
+  let statements = record_migration(migration, MigrationDirection::Up).await?;
+  let digest = Sha256::digest(statements.join(";\n").as_bytes());
+  Ok(digest.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+/// Checksums of `Migrator::migrations()` keyed by name. Migrations which cannot be recorded are skipped
+pub async fn current_checksums() -> HashMap<String, String> {
+  let mut checksums = HashMap::new();
+  for migration in Migrator::migrations() {
+    match migration_checksum(migration.as_ref()).await {
+      Ok(checksum) => {
+        checksums.insert(migration.name().to_string(), checksum);
+      },
+      Err(e) => warn!("Checksum of {} is not verified: {}", migration.name(), e),
+    }
+  }
+  checksums
+}
+
+/// Checksums recorded when migrations were applied, keyed by name. Does not create checksum table
+pub async fn stored_checksums(connection: &DatabaseConnection) -> Result<HashMap<String, String>, DbErr> {
+  if !SchemaManager::new(connection).has_table(CHECKSUM_TABLE).await? {
+    return Ok(HashMap::new());
+  }
+
+  let rows = connection
+    .query_all(Statement::from_string(
+      DatabaseBackend::Postgres,
+      format!("SELECT version, checksum FROM {}", CHECKSUM_TABLE),
+    ))
+    .await?;
+
+  let mut checksums = HashMap::new();
+  for row in rows {
+    checksums.insert(row.try_get::<String>("", "version")?, row.try_get::<String>("", "checksum")?);
+  }
+  Ok(checksums)
+}
+
+/// Names of migrations whose recorded checksum differs from current one, in order of `Migrator::migrations()`
+pub fn changed_migrations(stored: &HashMap<String, String>, current: &HashMap<String, String>) -> Vec<String> {
+  Migrator::migrations()
+    .iter()
+    .map(|migration| migration.name().to_string())
+    .filter(|name| matches!((stored.get(name), current.get(name)), (Some(stored), Some(current)) if stored != current))
+    .collect()
+}
+
+/// Bring checksum table in line with applied migrations: record checksums of newly applied migrations and forget
+/// rolled back ones. Rebaseline overwrites recorded checksums of every applied migration with current ones
+pub async fn record_checksums(
+  connection: &DatabaseConnection,
+  applied: &[String],
+  stored: &HashMap<String, String>,
+  current: &HashMap<String, String>,
+  rebaseline: bool,
+) -> Result<(), DbErr> {
+  connection
+    .execute_unprepared(&format!(
+      "CREATE TABLE IF NOT EXISTS {} (version varchar PRIMARY KEY, checksum varchar NOT NULL)",
+      CHECKSUM_TABLE
+    ))
+    .await?;
+
+  for version in applied.iter().filter(|version| rebaseline || !stored.contains_key(*version)) {
+    if let Some(checksum) = current.get(version) {
+      connection
+        .execute(Statement::from_sql_and_values(
+          DatabaseBackend::Postgres,
+          &format!(
+            "INSERT INTO {} (version, checksum) VALUES ($1, $2) \
+             ON CONFLICT (version) DO UPDATE SET checksum = EXCLUDED.checksum",
+            CHECKSUM_TABLE
+          ),
+          [version.clone().into(), checksum.clone().into()],
+        ))
+        .await?;
+    }
+  }
+
+  for version in stored.keys().filter(|version| !applied.contains(version)) {
+    connection
+      .execute(Statement::from_sql_and_values(
+        DatabaseBackend::Postgres,
+        &format!("DELETE FROM {} WHERE version = $1", CHECKSUM_TABLE),
+        [version.clone().into()],
+      ))
+      .await?;
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod checksum_test {
+  use super::{changed_migrations, current_checksums, migration_checksum};
+  use crate::migrator::Migrator;
+  use futures::executor::block_on;
+  use sea_orm_migration::MigratorTrait;
+  use std::collections::HashMap;
+
+  #[test]
+  fn checksum_is_stable() {
+    let migrations = Migrator::migrations();
+    let first = block_on(migration_checksum(migrations[0].as_ref())).unwrap();
+
+    assert_eq!(first.len(), 64);
+    assert_eq!(first, block_on(migration_checksum(migrations[0].as_ref())).unwrap());
+    assert_ne!(first, block_on(migration_checksum(migrations[1].as_ref())).unwrap());
+  }
+
+  #[test]
+  fn changed_only_if_recorded_differs() {
+    let current = block_on(current_checksums());
+    let bakery = String::from("m_20220602_000001_create_bakery_table");
+    let chef = String::from("m_20220602_000002_create_chef_table");
+
+    let stored = HashMap::from([(bakery.clone(), current[&bakery].clone()), (chef.clone(), String::from("edited"))]);
+    assert_eq!(changed_migrations(&stored, &current), vec![chef]);
+    assert!(changed_migrations(&HashMap::new(), &current).is_empty());
+  }
+}
@@ -0,0 +1,431 @@
+use std::fmt;
+
+use sea_orm::sea_query::{ColumnType, TableRef};
+use sea_orm::{
+  ColumnTrait, ConnectionTrait, DatabaseBackend, DatabaseConnection, DbErr, EntityTrait, IdenStatic, Identity,
+  Iterable, PrimaryKeyToColumn, RelationTrait, Statement,
+};
+
+use super::BOOKKEEPING_TABLES;
+#[cfg(feature = "entities")]
+use crate::entities::all_schemas;
+
+/// Columns of tables in current schema with Postgres type name and nullability
+const COLUMNS_QUERY: &str = "SELECT table_name::varchar, column_name::varchar, udt_name::varchar, \
+  is_nullable = 'YES' AS nullable \
+  FROM information_schema.columns \
+  WHERE table_schema = current_schema() \
+  ORDER BY table_name, ordinal_position";
+
+/// Primary key ("p") and foreign key ("f") columns of tables in current schema
+const KEYS_QUERY: &str = "SELECT c.relname::varchar AS table_name, con.contype::varchar AS key_type, \
+  a.attname::varchar AS column_name, fc.relname::varchar AS foreign_table, fa.attname::varchar AS foreign_column \
+  FROM pg_catalog.pg_constraint con \
+  JOIN pg_catalog.pg_class c ON c.oid = con.conrelid \
+  JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace \
+  CROSS JOIN LATERAL unnest(con.conkey, con.confkey) WITH ORDINALITY AS k(attnum, foreign_attnum, position) \
+  JOIN pg_catalog.pg_attribute a ON a.attrelid = con.conrelid AND a.attnum = k.attnum \
+  LEFT JOIN pg_catalog.pg_class fc ON fc.oid = con.confrelid \
+  LEFT JOIN pg_catalog.pg_attribute fa ON fa.attrelid = con.confrelid AND fa.attnum = k.foreign_attnum \
+  WHERE n.nspname = current_schema() AND con.contype IN ('p', 'f') \
+  ORDER BY c.relname, con.conname, k.position";
+
+/// Column of entity or DB table. Type is Postgres type name, e.g. "int4" or "varchar".
+/// None means type of entity column has no Postgres counterpart known to drift check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColumnSchema {
+  pub name: String,
+  pub column_type: Option<String>,
+  pub nullable: bool,
+}
+
+/// Column referencing column of another table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForeignKey {
+  pub column: String,
+  pub foreign_table: String,
+  pub foreign_column: String,
+}
+
+impl fmt::Display for ForeignKey {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{} -> {}.{}", self.column, self.foreign_table, self.foreign_column)
+  }
+}
+
+/// Table as described by entity or introspected from DB
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TableSchema {
+  pub name: String,
+  pub columns: Vec<ColumnSchema>,
+  pub primary_key: Vec<String>,
+  pub foreign_keys: Vec<ForeignKey>,
+}
+
+impl TableSchema {
+  /// Describe table of entity by `ColumnTrait::def()`, primary key and `belongs_to` relations
+  pub fn of<E: EntityTrait>(entity: E) -> TableSchema {
+    TableSchema {
+      name: entity.table_name().to_string(),
+      columns: E::Column::iter()
+        .map(|column| {
+          let def = column.def();
+          ColumnSchema {
+            name: column.as_str().to_string(),
+            column_type: postgres_type(def.get_column_type()),
+            nullable: def.is_null(),
+          }
+        })
+        .collect(),
+      primary_key: E::PrimaryKey::iter().map(|key| key.into_column().as_str().to_string()).collect(),
+      foreign_keys: E::Relation::iter()
+        .map(|relation| relation.def())
+        .filter(|def| !def.is_owner)
+        .flat_map(|def| {
+          let foreign_table = table_name(&def.to_tbl);
+          identity_names(&def.from_col).into_iter().zip(identity_names(&def.to_col)).map(
+            move |(column, foreign_column)| ForeignKey { column, foreign_table: foreign_table.clone(), foreign_column },
+          )
+        })
+        .collect(),
+    }
+  }
+}
+
+/// Uses to describe difference between entities and DB:
+/// * Drift::MissingTable - Entity table is absent in DB
+/// * Drift::ExtraTable - DB table has no entity
+/// * Drift::MissingColumn - Entity column is absent in DB
+/// * Drift::ExtraColumn - DB column is absent in entity
+/// * Drift::TypeMismatch - Column types differ
+/// * Drift::NullabilityMismatch - Column is nullable in one of them only
+/// * Drift::PrimaryKeyMismatch - Primary key columns differ
+/// * Drift::MissingForeignKey - Entity relation has no foreign key in DB
+/// * Drift::ExtraForeignKey - DB foreign key has no entity relation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Drift {
+  MissingTable(String),
+  ExtraTable(String),
+  MissingColumn { table: String, column: String },
+  ExtraColumn { table: String, column: String },
+  TypeMismatch { table: String, column: String, entity: String, db: String },
+  NullabilityMismatch { table: String, column: String, entity_nullable: bool },
+  PrimaryKeyMismatch { table: String, entity: Vec<String>, db: Vec<String> },
+  MissingForeignKey { table: String, foreign_key: ForeignKey },
+  ExtraForeignKey { table: String, foreign_key: ForeignKey },
+}
+
+impl fmt::Display for Drift {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let nullability = |nullable: &bool| if *nullable { "nullable" } else { "not null" };
+    match self {
+      Drift::MissingTable(table) => write!(f, "{}: table is missing in DB", table),
+      Drift::ExtraTable(table) => write!(f, "{}: table has no entity", table),
+      Drift::MissingColumn { table, column } => write!(f, "{}.{}: column is missing in DB", table, column),
+      Drift::ExtraColumn { table, column } => write!(f, "{}.{}: column is missing in entity", table, column),
+      Drift::TypeMismatch { table, column, entity, db } => {
+        write!(f, "{}.{}: type is {} in entity, {} in DB", table, column, entity, db)
+      },
+      Drift::NullabilityMismatch { table, column, entity_nullable } => write!(
+        f,
+        "{}.{}: column is {} in entity, {} in DB",
+        table,
+        column,
+        nullability(entity_nullable),
+        nullability(&!entity_nullable)
+      ),
+      Drift::PrimaryKeyMismatch { table, entity, db } => {
+        write!(f, "{}: primary key is ({}) in entity, ({}) in DB", table, entity.join(", "), db.join(", "))
+      },
+      Drift::MissingForeignKey { table, foreign_key } => {
+        write!(f, "{}: foreign key {} is missing in DB", table, foreign_key)
+      },
+      Drift::ExtraForeignKey { table, foreign_key } => {
+        write!(f, "{}: foreign key {} has no entity relation", table, foreign_key)
+      },
+    }
+  }
+}
+
+/// Tables of generated entities, listed by `all_schemas()` of entities module
+#[cfg(feature = "entities")]
+pub fn entity_schemas() -> Vec<TableSchema> {
+  all_schemas()
+}
+
+/// Introspect tables of current schema. Bookkeeping tables are left out
+pub async fn db_schemas(connection: &DatabaseConnection) -> Result<Vec<TableSchema>, DbErr> {
+  let mut tables: Vec<TableSchema> = Vec::new();
+  for row in query(connection, COLUMNS_QUERY).await? {
+    let column = ColumnSchema {
+      name: row.try_get("", "column_name")?,
+      column_type: Some(row.try_get("", "udt_name")?),
+      nullable: row.try_get("", "nullable")?,
+    };
+    table_entry(&mut tables, row.try_get("", "table_name")?).columns.push(column);
+  }
+  for row in query(connection, KEYS_QUERY).await? {
+    let table = table_entry(&mut tables, row.try_get("", "table_name")?);
+    let column: String = row.try_get("", "column_name")?;
+    if row.try_get::<String>("", "key_type")? == "p" {
+      table.primary_key.push(column);
+    } else {
+      table.foreign_keys.push(ForeignKey {
+        column,
+        foreign_table: row.try_get("", "foreign_table")?,
+        foreign_column: row.try_get("", "foreign_column")?,
+      });
+    }
+  }
+
+  tables.retain(|table| !BOOKKEEPING_TABLES.contains(&table.name.as_str()));
+  Ok(tables)
+}
+
+/// Compare entities with DB tables of current schema, see `schema_drift`
+#[cfg(feature = "entities")]
+pub async fn check_drift(connection: &DatabaseConnection) -> Result<Vec<Drift>, DbErr> {
+  Ok(schema_drift(&entity_schemas(), &db_schemas(connection).await?))
+}
+
+/// Every difference between tables described by entities and DB tables
+pub fn schema_drift(entities: &[TableSchema], db: &[TableSchema]) -> Vec<Drift> {
+  let mut drift = Vec::new();
+  for entity in entities {
+    let table = match db.iter().find(|table| table.name == entity.name) {
+      Some(table) => table,
+      None => {
+        drift.push(Drift::MissingTable(entity.name.clone()));
+        continue;
+      },
+    };
+    drift.extend(table_drift(entity, table));
+  }
+  for table in db.iter().filter(|table| !entities.iter().any(|entity| entity.name == table.name)) {
+    drift.push(Drift::ExtraTable(table.name.clone()));
+  }
+  drift
+}
+
+fn table_drift(entity: &TableSchema, table: &TableSchema) -> Vec<Drift> {
+  let name = || entity.name.clone();
+  let mut drift = Vec::new();
+  for column in &entity.columns {
+    let db_column = match table.columns.iter().find(|db_column| db_column.name == column.name) {
+      Some(db_column) => db_column,
+      None => {
+        drift.push(Drift::MissingColumn { table: name(), column: column.name.clone() });
+        continue;
+      },
+    };
+    if let (Some(entity_type), Some(db_type)) = (&column.column_type, &db_column.column_type) {
+      if entity_type != db_type {
+        drift.push(Drift::TypeMismatch {
+          table: name(),
+          column: column.name.clone(),
+          entity: entity_type.clone(),
+          db: db_type.clone(),
+        });
+      }
+    }
+    if column.nullable != db_column.nullable {
+      drift.push(Drift::NullabilityMismatch {
+        table: name(),
+        column: column.name.clone(),
+        entity_nullable: column.nullable,
+      });
+    }
+  }
+  for column in table.columns.iter().filter(|column| !entity.columns.iter().any(|other| other.name == column.name)) {
+    drift.push(Drift::ExtraColumn { table: name(), column: column.name.clone() });
+  }
+
+  if entity.primary_key != table.primary_key {
+    drift.push(Drift::PrimaryKeyMismatch {
+      table: name(),
+      entity: entity.primary_key.clone(),
+      db: table.primary_key.clone(),
+    });
+  }
+  for foreign_key in entity.foreign_keys.iter().filter(|key| !table.foreign_keys.contains(key)) {
+    drift.push(Drift::MissingForeignKey { table: name(), foreign_key: foreign_key.clone() });
+  }
+  for foreign_key in table.foreign_keys.iter().filter(|key| !entity.foreign_keys.contains(key)) {
+    drift.push(Drift::ExtraForeignKey { table: name(), foreign_key: foreign_key.clone() });
+  }
+  drift
+}
+
+/// Postgres type name (`udt_name` of information_schema) column type is stored as
+fn postgres_type(column_type: &ColumnType) -> Option<String> {
+  let name = match column_type {
+    ColumnType::Char(_) => "bpchar",
+    ColumnType::String(_) => "varchar",
+    ColumnType::Text => "text",
+    ColumnType::TinyInteger | ColumnType::SmallInteger | ColumnType::TinyUnsigned | ColumnType::SmallUnsigned => "int2",
+    ColumnType::Integer | ColumnType::Unsigned => "int4",
+    ColumnType::BigInteger | ColumnType::BigUnsigned => "int8",
+    ColumnType::Float => "float4",
+    ColumnType::Double => "float8",
+    ColumnType::Decimal(_) => "numeric",
+    ColumnType::Money(_) => "money",
+    ColumnType::DateTime | ColumnType::Timestamp => "timestamp",
+    ColumnType::TimestampWithTimeZone => "timestamptz",
+    ColumnType::Time => "time",
+    ColumnType::Date => "date",
+    ColumnType::Interval(_, _) => "interval",
+    ColumnType::Binary(_) | ColumnType::VarBinary(_) => "bytea",
+    ColumnType::Bit(_) => "bit",
+    ColumnType::VarBit(_) => "varbit",
+    ColumnType::Boolean => "bool",
+    ColumnType::Json => "json",
+    ColumnType::JsonBinary => "jsonb",
+    ColumnType::Uuid => "uuid",
+    ColumnType::Cidr => "cidr",
+    ColumnType::Inet => "inet",
+    ColumnType::MacAddr => "macaddr",
+    ColumnType::Enum { name, .. } => return Some(name.to_string()),
+    ColumnType::Custom(name) => return Some(name.to_string().to_lowercase()),
+    ColumnType::Array(column_type) => return postgres_type(column_type).map(|name| format!("_{}", name)),
+    _ => return None,
+  };
+  Some(name.to_string())
+}
+
+fn table_name(table: &TableRef) -> String {
+  match table {
+    TableRef::Table(name) | TableRef::SchemaTable(_, name) | TableRef::DatabaseSchemaTable(_, _, name) => {
+      name.to_string()
+    },
+    _ => String::new(),
+  }
+}
+
+fn identity_names(identity: &Identity) -> Vec<String> {
+  match identity {
+    Identity::Unary(a) => vec![a.to_string()],
+    Identity::Binary(a, b) => vec![a.to_string(), b.to_string()],
+    Identity::Ternary(a, b, c) => vec![a.to_string(), b.to_string(), c.to_string()],
+  }
+}
+
+fn table_entry(tables: &mut Vec<TableSchema>, name: String) -> &mut TableSchema {
+  let index = match tables.iter().position(|table| table.name == name) {
+    Some(index) => index,
+    None => {
+      tables.push(TableSchema { name, ..Default::default() });
+      tables.len() - 1
+    },
+  };
+  &mut tables[index]
+}
+
+async fn query(connection: &DatabaseConnection, sql: &str) -> Result<Vec<sea_orm::QueryResult>, DbErr> {
+  connection.query_all(Statement::from_string(DatabaseBackend::Postgres, sql.to_string())).await
+}
+
+#[cfg(all(test, feature = "entities"))]
+mod drift_test {
+  use super::{entity_schemas, schema_drift, ColumnSchema, Drift, ForeignKey, TableSchema};
+  use std::fs::read_dir;
+
+  fn column(name: &str, column_type: &str, nullable: bool) -> ColumnSchema {
+    ColumnSchema { name: name.to_string(), column_type: Some(column_type.to_string()), nullable }
+  }
+
+  /// DB tables created by default migrations
+  fn migrated() -> Vec<TableSchema> {
+    vec![
+      TableSchema {
+        name: String::from("bakery"),
+        columns: vec![
+          column("id", "int4", false),
+          column("name", "varchar", false),
+          column("profit_margin", "float8", false),
+        ],
+        primary_key: vec![String::from("id")],
+        foreign_keys: vec![],
+      },
+      TableSchema {
+        name: String::from("chef"),
+        columns: vec![
+          column("id", "int4", false),
+          column("name", "varchar", false),
+          column("contact_details", "json", true),
+          column("bakery_id", "int4", false),
+        ],
+        primary_key: vec![String::from("id")],
+        foreign_keys: vec![ForeignKey {
+          column: String::from("bakery_id"),
+          foreign_table: String::from("bakery"),
+          foreign_column: String::from("id"),
+        }],
+      },
+    ]
+  }
+
+  #[test]
+  fn entities_match_migrations() {
+    assert_eq!(schema_drift(&entity_schemas(), &migrated()), vec![]);
+  }
+
+  #[test]
+  fn every_entity_module_is_listed() {
+    let mut modules: Vec<String> = read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/src/entities"))
+      .unwrap()
+      .filter_map(|entry| entry.unwrap().file_name().to_string_lossy().strip_suffix(".rs").map(String::from))
+      .filter(|module| module != "mod" && module != "prelude")
+      .collect();
+    modules.sort();
+    let mut listed: Vec<String> = entity_schemas().into_iter().map(|schema| schema.name).collect();
+    listed.sort();
+
+    assert_eq!(modules, listed);
+  }
+
+  #[test]
+  fn drift_of_columns() {
+    let mut db = migrated();
+    db[1].columns = vec![
+      column("id", "int8", false),
+      column("name", "varchar", true),
+      column("bakery_id", "int4", false),
+      column("rating", "int2", true),
+    ];
+    db[1].foreign_keys.clear();
+
+    let drift: Vec<String> = schema_drift(&entity_schemas(), &db).iter().map(|drift| drift.to_string()).collect();
+
+    assert_eq!(
+      drift,
+      vec![
+        "chef.id: type is int4 in entity, int8 in DB",
+        "chef.name: column is not null in entity, nullable in DB",
+        "chef.contact_details: column is missing in DB",
+        "chef.rating: column is missing in entity",
+        "chef: foreign key bakery_id -> bakery.id is missing in DB",
+      ]
+    );
+  }
+
+  #[test]
+  fn drift_of_tables() {
+    let mut db = migrated();
+    db.remove(0);
+    db.push(TableSchema { name: String::from("oven"), primary_key: vec![String::from("id")], ..Default::default() });
+    db[0].primary_key.push(String::from("name"));
+
+    assert_eq!(
+      schema_drift(&entity_schemas(), &db),
+      vec![
+        Drift::MissingTable(String::from("bakery")),
+        Drift::PrimaryKeyMismatch {
+          table: String::from("chef"),
+          entity: vec![String::from("id")],
+          db: vec![String::from("id"), String::from("name")],
+        },
+        Drift::ExtraTable(String::from("oven")),
+      ]
+    );
+  }
+}
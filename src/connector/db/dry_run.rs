@@ -0,0 +1,137 @@
+use std::sync::{Arc, Mutex};
+
+use sea_orm::{
+  DatabaseBackend, DatabaseConnection, DbErr, EntityTrait, ExecResult, MockDatabaseConnection, MockDatabaseTrait,
+  QueryOrder, QueryResult, Statement, Transaction,
+};
+use sea_orm_migration::{seaql_migrations, MigrationTrait, SchemaManager};
+use serde::Serialize;
+use sqlx::postgres::PgQueryResult;
+
+use super::MigrationDirection;
+
+/// SQL statements single migration would run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MigrationPlan {
+  pub name: String,
+  pub direction: MigrationDirection,
+  pub statements: Vec<String>,
+}
+
+/// Mock DB accepting every statement. Executed statements are recorded, queries return no rows
+#[derive(Debug, Default)]
+struct Recorder {
+  statements: Arc<Mutex<Vec<Statement>>>,
+}
+
+impl MockDatabaseTrait for Recorder {
+  fn execute(&mut self, _counter: usize, stmt: Statement) -> Result<ExecResult, DbErr> {
+    self.statements.lock().unwrap().push(stmt);
+    Ok(PgQueryResult::default().into())
+  }
+
+  fn query(&mut self, _counter: usize, _stmt: Statement) -> Result<Vec<QueryResult>, DbErr> {
+    Ok(Vec::new())
+  }
+
+  fn begin(&mut self) {}
+
+  fn commit(&mut self) {}
+
+  fn rollback(&mut self) {}
+
+  fn drain_transaction_log(&mut self) -> Vec<Transaction> {
+    Vec::new()
+  }
+
+  fn get_database_backend(&self) -> DatabaseBackend {
+    DatabaseBackend::Postgres
+  }
+}
+
+/// Run migration's up or down against recording connection and return SQL it executed.
+/// Migrations depending on introspection (e.g. `manager.has_table`) cannot be recorded
+pub async fn record_migration(
+  migration: &dyn MigrationTrait,
+  direction: MigrationDirection,
+) -> Result<Vec<String>, DbErr> {
+  let recorder = Recorder::default();
+  let statements = Arc::clone(&recorder.statements);
+  let connection = DatabaseConnection::MockDatabaseConnection(Arc::new(MockDatabaseConnection::new(recorder)));
+
+  let manager = SchemaManager::new(&connection);
+  match direction {
+    MigrationDirection::Up => migration.up(&manager).await?,
+    MigrationDirection::Down => migration.down(&manager).await?,
+  }
+
+  let statements = statements.lock().unwrap().iter().map(|statement| statement.to_string()).collect();
+  Ok(statements)
+}
+
+/// Migrations applied to DB ordered by version, which is order of `Migrator::migrations()`.
+/// Unlike `Migrator::get_migration_models` does not create seaql_migrations table, so it is safe for dry run
+/// and read-only DB
+pub async fn applied_migrations(connection: &DatabaseConnection) -> Result<Vec<seaql_migrations::Model>, DbErr> {
+  if !SchemaManager::new(connection).has_table("seaql_migrations").await? {
+    return Ok(Vec::new());
+  }
+
+  seaql_migrations::Entity::find().order_by_asc(seaql_migrations::Column::Version).all(connection).await
+}
+
+/// Versions applied to DB ordered by version, see `applied_migrations`
+pub async fn applied_versions(connection: &DatabaseConnection) -> Result<Vec<String>, DbErr> {
+  Ok(applied_migrations(connection).await?.into_iter().map(|model| model.version).collect())
+}
+
+/// Format plans as SQL script, one commented block per migration
+pub fn plan_sql(plans: &[MigrationPlan]) -> String {
+  let mut sql = String::new();
+  for plan in plans {
+    sql.push_str(&format!("-- {} ({})\n", plan.name, plan.direction));
+    for statement in &plan.statements {
+      sql.push_str(statement);
+      sql.push_str(";\n");
+    }
+    sql.push('\n');
+  }
+  sql
+}
+
+#[cfg(test)]
+mod dry_run_test {
+  use super::{plan_sql, record_migration, MigrationPlan};
+  use crate::connector::db::MigrationDirection;
+  use crate::migrator::Migrator;
+  use futures::executor::block_on;
+  use sea_orm_migration::MigratorTrait;
+
+  #[test]
+  fn record_up() {
+    let migrations = Migrator::migrations();
+    let statements = block_on(record_migration(migrations[0].as_ref(), MigrationDirection::Up)).unwrap();
+
+    assert_eq!(statements.len(), 1);
+    assert!(statements[0].starts_with("CREATE TABLE \"bakery\""));
+  }
+
+  #[test]
+  fn record_down() {
+    let migrations = Migrator::migrations();
+    let statements = block_on(record_migration(migrations[1].as_ref(), MigrationDirection::Down)).unwrap();
+
+    assert_eq!(statements, vec![String::from("DROP TABLE \"chef\"")]);
+  }
+
+  #[test]
+  fn plan_as_sql() {
+    let plans = vec![MigrationPlan {
+      name: String::from("m_1"),
+      direction: MigrationDirection::Down,
+      statements: vec![String::from("DROP TABLE \"chef\"")],
+    }];
+
+    assert_eq!(plan_sql(&plans), "-- m_1 (down)\nDROP TABLE \"chef\";\n\n");
+  }
+}
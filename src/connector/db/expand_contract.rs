@@ -0,0 +1,417 @@
+use std::fmt;
+
+use chrono::{DateTime, TimeZone, Utc};
+use sea_orm::{ConnectionTrait, DatabaseBackend, DatabaseConnection, DbErr, Statement};
+use sea_orm_migration::{SchemaManager, SchemaManagerConnection};
+use serde::Serialize;
+
+use super::backfill::Backfill;
+use super::provision::{quote_ident, quote_literal};
+use super::{MigrationDirection, MISSING_DOWN};
+
+/// Table holding phase of every expand/contract change, see `ColumnChange`
+pub const CHANGE_TABLE: &str = "seaql_expand_contract";
+
+/// Phase of expand/contract change, entered in order, usually over several releases:
+/// * ChangePhase::AddColumn - New nullable column is added. Old releases do not know it
+/// * ChangePhase::DualWrite - Trigger fills new column from old one on every write of old releases
+/// * ChangePhase::Backfill - New column of existing rows is filled from old one in batches, see `Backfill`.
+///   Migration entering it must run with `TransactionMode::None`
+/// * ChangePhase::SwitchReads - Release reading and writing new column is rolled out. Nothing is changed in DB
+/// * ChangePhase::Contract - Trigger and old column are dropped, once no release uses old column
+///
+/// Rolling back a phase returns the change to the previous one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangePhase {
+  AddColumn,
+  DualWrite,
+  Backfill,
+  SwitchReads,
+  Contract,
+}
+
+const PHASES: [ChangePhase; 5] = [
+  ChangePhase::AddColumn,
+  ChangePhase::DualWrite,
+  ChangePhase::Backfill,
+  ChangePhase::SwitchReads,
+  ChangePhase::Contract,
+];
+
+impl ChangePhase {
+  /// Build ChangePhase from its name, e.g. "dual_write"
+  pub fn from_string(value: &str) -> Option<ChangePhase> {
+    PHASES.into_iter().find(|phase| phase.to_string() == value)
+  }
+
+  /// Phase the change must be in to enter this one. None for the first phase
+  pub fn previous(&self) -> Option<ChangePhase> {
+    PHASES.into_iter().take_while(|phase| phase != self).last()
+  }
+}
+
+impl fmt::Display for ChangePhase {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ChangePhase::AddColumn => write!(f, "add_column"),
+      ChangePhase::DualWrite => write!(f, "dual_write"),
+      ChangePhase::Backfill => write!(f, "backfill"),
+      ChangePhase::SwitchReads => write!(f, "switch_reads"),
+      ChangePhase::Contract => write!(f, "contract"),
+    }
+  }
+}
+
+/// Column replaced by expand/contract without downtime: new column is added next to old one and kept in sync,
+/// until old one is dropped. Expressions are SQL over columns of the row: forward computes new column from old
+/// one, reverse computes old column from new one. Without reverse Contract cannot be rolled back.
+/// Every phase is run by its own migration, checking the change is in the previous phase. Initialize by running:
+/// ```
+/// use rust_orm_template::connector::db::{ChangePhase, ColumnChange};
+/// use sea_orm_migration::prelude::*;
+///
+/// const CONTACT: ColumnChange = ColumnChange {
+///   name: "chef_contact_jsonb",
+///   table: "chef",
+///   old_column: "contact_details",
+///   old_type: "json",
+///   new_column: "contact",
+///   new_type: "jsonb",
+///   forward: "contact_details::jsonb",
+///   reverse: Some("contact::json"),
+/// };
+///
+/// async fn up(manager: &SchemaManager<'_>) -> Result<(), DbErr> {
+///   CONTACT.up(manager, ChangePhase::AddColumn).await?;
+///   CONTACT.up(manager, ChangePhase::DualWrite).await
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColumnChange {
+  pub name: &'static str,
+  pub table: &'static str,
+  pub old_column: &'static str,
+  pub old_type: &'static str,
+  pub new_column: &'static str,
+  pub new_type: &'static str,
+  pub forward: &'static str,
+  pub reverse: Option<&'static str>,
+}
+
+impl ColumnChange {
+  /// Enter phase. Fails if the change is not in the previous phase. Backfill phase runs `ColumnChange::backfill`,
+  /// so it fails unless migration runs with `TransactionMode::None`
+  pub async fn up(&self, manager: &SchemaManager<'_>, phase: ChangePhase) -> Result<(), DbErr> {
+    let backfill = self.backfill();
+    let backfill = if phase == ChangePhase::Backfill { Some(&backfill) } else { None };
+    self.run(manager, phase, MigrationDirection::Up, backfill).await
+  }
+
+  /// Enter Backfill phase running given backfill, e.g. `ColumnChange::backfill` with tuned batches.
+  /// Fails unless migration runs with `TransactionMode::None`
+  #[allow(dead_code)]
+  pub async fn backfill_up(&self, manager: &SchemaManager<'_>, backfill: &Backfill) -> Result<(), DbErr> {
+    self.run(manager, ChangePhase::Backfill, MigrationDirection::Up, Some(backfill)).await
+  }
+
+  /// Roll back phase, returning the change to the previous one. Fails if the change is not in the phase
+  pub async fn down(&self, manager: &SchemaManager<'_>, phase: ChangePhase) -> Result<(), DbErr> {
+    self.run(manager, phase, MigrationDirection::Down, None).await
+  }
+
+  /// Batched backfill of new column from old one, skipping rows filled already
+  pub fn backfill(&self) -> Backfill {
+    let new = quote_ident(self.new_column);
+    Backfill::new(self.name, self.table, &format!("{} = ({})", new, self.forward))
+      .with_filter(&format!("{} IS NULL", new))
+  }
+
+  /// Run statements of phase, with backfill before update of the phase
+  async fn run(
+    &self,
+    manager: &SchemaManager<'_>,
+    phase: ChangePhase,
+    direction: MigrationDirection,
+    backfill: Option<&Backfill>,
+  ) -> Result<(), DbErr> {
+    let connection = manager.get_connection();
+    let backfill = match (backfill, connection) {
+      (Some(backfill), SchemaManagerConnection::Connection(connection)) => Some((backfill, *connection)),
+      (Some(_), SchemaManagerConnection::Transaction(_)) => {
+        return Err(DbErr::Custom(format!(
+          "Backfill of change {} cannot commit its batches in transaction, run migration with TransactionMode::None",
+          self.name
+        )));
+      },
+      (None, _) => None,
+    };
+    let statements = self.statements(phase, direction)?;
+    if let Some((record, statements)) = statements.split_last() {
+      for statement in statements {
+        connection.execute_unprepared(statement).await?;
+      }
+      if let Some((backfill, connection)) = backfill {
+        backfill.run(connection).await?;
+      }
+      connection.execute_unprepared(record).await?;
+    }
+    Ok(())
+  }
+
+  /// SQL entering or rolling back phase: check of current phase, schema change and update of the phase.
+  /// Phase is checked by SQL, so migration is recorded by `record_migration` as is.
+  /// Backfill itself is run by `ColumnChange::up`, its rollback forgets backfill progress
+  pub fn statements(&self, phase: ChangePhase, direction: MigrationDirection) -> Result<Vec<String>, DbErr> {
+    let (from, to) = match direction {
+      MigrationDirection::Up => (phase.previous(), Some(phase)),
+      MigrationDirection::Down => (Some(phase), phase.previous()),
+    };
+
+    let mut statements = vec![
+      format!(
+        "CREATE TABLE IF NOT EXISTS {} (name varchar PRIMARY KEY, phase varchar NOT NULL, updated_at bigint NOT NULL)",
+        CHANGE_TABLE
+      ),
+      self.phase_check(from, phase, direction),
+    ];
+    statements.extend(match (phase, direction) {
+      (ChangePhase::AddColumn, MigrationDirection::Up) => vec![self.add_column(self.new_column, self.new_type)],
+      (ChangePhase::AddColumn, MigrationDirection::Down) => vec![self.drop_column(self.new_column)],
+      (ChangePhase::DualWrite, MigrationDirection::Up) => self.create_trigger(),
+      (ChangePhase::DualWrite, MigrationDirection::Down) => self.drop_trigger(),
+      (ChangePhase::Backfill, MigrationDirection::Down) => self.backfill().reset_statements(),
+      (ChangePhase::Contract, MigrationDirection::Up) => {
+        let mut statements = self.drop_trigger();
+        statements.push(self.drop_column(self.old_column));
+        statements
+      },
+      (ChangePhase::Contract, MigrationDirection::Down) => {
+        let reverse = self.reverse.ok_or_else(|| DbErr::Migration(MISSING_DOWN.to_string()))?;
+        let mut statements = vec![
+          self.add_column(self.old_column, self.old_type),
+          format!("UPDATE {} SET {} = ({})", quote_ident(self.table), quote_ident(self.old_column), reverse),
+        ];
+        statements.extend(self.create_trigger());
+        statements
+      },
+      (ChangePhase::Backfill, MigrationDirection::Up) | (ChangePhase::SwitchReads, _) => Vec::new(),
+    });
+    statements.push(match to {
+      Some(to) => format!(
+        "INSERT INTO {} (name, phase, updated_at) VALUES ({}, {}, extract(epoch FROM now())::bigint) \
+         ON CONFLICT (name) DO UPDATE SET phase = EXCLUDED.phase, updated_at = EXCLUDED.updated_at",
+        CHANGE_TABLE,
+        quote_literal(self.name),
+        quote_literal(&to.to_string())
+      ),
+      None => format!("DELETE FROM {} WHERE name = {}", CHANGE_TABLE, quote_literal(self.name)),
+    });
+    Ok(statements)
+  }
+
+  /// Block raising error unless the change is in expected phase, None meaning the change is not started
+  fn phase_check(&self, expected: Option<ChangePhase>, phase: ChangePhase, direction: MigrationDirection) -> String {
+    let expected_text = expected.map(|expected| expected.to_string()).unwrap_or_else(|| String::from("none"));
+    let action = match direction {
+      MigrationDirection::Up => "enter",
+      MigrationDirection::Down => "roll back",
+    };
+    format!(
+      "DO $$ BEGIN IF (SELECT phase FROM {table} WHERE name = {name}) IS DISTINCT FROM {expected} THEN \
+       RAISE EXCEPTION {message}; END IF; END $$",
+      table = CHANGE_TABLE,
+      name = quote_literal(self.name),
+      expected = expected.map(|expected| quote_literal(&expected.to_string())).unwrap_or_else(|| String::from("NULL")),
+      message = quote_literal(&format!(
+        "Change {} must be in phase {} to {} phase {}",
+        self.name, expected_text, action, phase
+      ))
+    )
+  }
+
+  fn add_column(&self, column: &str, column_type: &str) -> String {
+    format!("ALTER TABLE {} ADD COLUMN {} {}", quote_ident(self.table), quote_ident(column), column_type)
+  }
+
+  fn drop_column(&self, column: &str) -> String {
+    format!("ALTER TABLE {} DROP COLUMN {}", quote_ident(self.table), quote_ident(column))
+  }
+
+  /// Name of dual-write trigger and its function
+  fn trigger(&self) -> String {
+    quote_ident(&format!("{}_{}_dual_write", self.table, self.new_column))
+  }
+
+  /// Trigger filling new column from old one on insert and on update of old one. Rows inserted with old column
+  /// unset, e.g. by release writing new column only, are left as is. Values are compared as text, as some types,
+  /// e.g. json, have no equality
+  fn create_trigger(&self) -> Vec<String> {
+    let old = quote_ident(self.old_column);
+    vec![
+      format!(
+        "CREATE OR REPLACE FUNCTION {trigger}() RETURNS trigger AS $$ BEGIN \
+         IF (TG_OP = 'INSERT' AND NEW.{old} IS NOT NULL) OR (TG_OP = 'UPDATE' AND NEW.{old}::text IS DISTINCT FROM OLD.{old}::text) \
+         THEN NEW.{new} := (SELECT {forward} FROM (SELECT (NEW).*) AS changed_row); END IF; RETURN NEW; END \
+         $$ LANGUAGE plpgsql",
+        trigger = self.trigger(),
+        old = old,
+        new = quote_ident(self.new_column),
+        forward = self.forward
+      ),
+      format!(
+        "CREATE TRIGGER {trigger} BEFORE INSERT OR UPDATE ON {table} FOR EACH ROW EXECUTE FUNCTION {trigger}()",
+        trigger = self.trigger(),
+        table = quote_ident(self.table)
+      ),
+    ]
+  }
+
+  fn drop_trigger(&self) -> Vec<String> {
+    vec![
+      format!("DROP TRIGGER IF EXISTS {} ON {}", self.trigger(), quote_ident(self.table)),
+      format!("DROP FUNCTION IF EXISTS {}()", self.trigger()),
+    ]
+  }
+}
+
+/// Phase of expand/contract change recorded in DB
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ChangeState {
+  pub name: String,
+  pub phase: ChangePhase,
+  pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// Phase of every started expand/contract change, in order of name. Does not create change table
+pub async fn change_states(connection: &DatabaseConnection) -> Result<Vec<ChangeState>, DbErr> {
+  if !SchemaManager::new(connection).has_table(CHANGE_TABLE).await? {
+    return Ok(Vec::new());
+  }
+
+  let rows = connection
+    .query_all(Statement::from_string(
+      DatabaseBackend::Postgres,
+      format!("SELECT name, phase, updated_at FROM {} ORDER BY name", CHANGE_TABLE),
+    ))
+    .await?;
+
+  let mut states = Vec::new();
+  for row in rows {
+    let phase: String = row.try_get("", "phase")?;
+    states.push(ChangeState {
+      name: row.try_get("", "name")?,
+      phase: ChangePhase::from_string(&phase)
+        .ok_or_else(|| DbErr::Custom(format!("Unknown phase {} of expand/contract change", phase)))?,
+      updated_at: Utc.timestamp_opt(row.try_get("", "updated_at")?, 0).single(),
+    });
+  }
+  Ok(states)
+}
+
+/// Format change states as human-readable table
+pub fn changes_table(states: &[ChangeState]) -> String {
+  if states.is_empty() {
+    return String::from("No expand/contract changes started\n");
+  }
+
+  let width = states.iter().map(|state| state.name.len()).max().unwrap_or(0).max("Change".len());
+  let mut table = format!("{:<width$}  {:<12}  {}\n", "Change", "Phase", "Updated at", width = width);
+  for state in states {
+    let updated_at = state.updated_at.map(|at| at.format("%Y-%m-%d %H:%M:%S UTC").to_string()).unwrap_or_default();
+    table.push_str(
+      format!("{:<width$}  {:<12}  {}", state.name, state.phase.to_string(), updated_at, width = width).trim_end(),
+    );
+    table.push('\n');
+  }
+  table
+}
+
+#[cfg(test)]
+mod expand_contract_test {
+  use super::{changes_table, ChangePhase, ChangeState, ColumnChange};
+  use crate::connector::db::{lint_statements, LintRule, MigrationDirection};
+  use chrono::{TimeZone, Utc};
+  use futures::executor::block_on;
+  use sea_orm::{DatabaseBackend, DbErr, MockDatabase, TransactionTrait};
+  use sea_orm_migration::SchemaManager;
+
+  const CONTACT: ColumnChange = ColumnChange {
+    name: "chef_contact_jsonb",
+    table: "chef",
+    old_column: "contact_details",
+    old_type: "json",
+    new_column: "contact",
+    new_type: "jsonb",
+    forward: "contact_details::jsonb",
+    reverse: None,
+  };
+
+  #[test]
+  fn phases_in_order() {
+    assert_eq!(ChangePhase::AddColumn.previous(), None);
+    assert_eq!(ChangePhase::Contract.previous(), Some(ChangePhase::SwitchReads));
+    assert_eq!(ChangePhase::from_string("dual_write"), Some(ChangePhase::DualWrite));
+    assert_eq!(ChangePhase::from_string("done"), None);
+  }
+
+  #[test]
+  fn backfill_in_transaction() {
+    let connection = MockDatabase::new(DatabaseBackend::Postgres).into_connection();
+    let transaction = block_on(connection.begin()).unwrap();
+
+    match block_on(CONTACT.up(&SchemaManager::new(&transaction), ChangePhase::Backfill)) {
+      Err(e) => assert!(e.to_string().contains("TransactionMode::None")),
+      Ok(_) => panic!(),
+    }
+    block_on(transaction.rollback()).unwrap();
+    assert!(!format!("{:?}", connection.into_transaction_log()).contains("seaql_expand_contract"));
+  }
+
+  #[test]
+  fn add_column_statements() {
+    let statements = CONTACT.statements(ChangePhase::AddColumn, MigrationDirection::Up).unwrap();
+
+    assert_eq!(statements.len(), 4);
+    assert!(statements[1].contains(
+      "IS DISTINCT FROM NULL THEN RAISE EXCEPTION 'Change chef_contact_jsonb must be in phase none to enter phase \
+       add_column'"
+    ));
+    assert_eq!(statements[2], "ALTER TABLE \"chef\" ADD COLUMN \"contact\" jsonb");
+    assert!(statements[3].contains("VALUES ('chef_contact_jsonb', 'add_column', "));
+  }
+
+  #[test]
+  fn dual_write_and_contract_statements() {
+    let dual_write = CONTACT.statements(ChangePhase::DualWrite, MigrationDirection::Up).unwrap();
+    assert!(dual_write[1].contains("IS DISTINCT FROM 'add_column'"));
+    assert!(dual_write[2].contains("NEW.\"contact\" := (SELECT contact_details::jsonb FROM (SELECT (NEW).*)"));
+    assert!(dual_write[3].starts_with("CREATE TRIGGER \"chef_contact_dual_write\" BEFORE INSERT OR UPDATE ON \"chef\""));
+
+    let rollback = CONTACT.statements(ChangePhase::AddColumn, MigrationDirection::Down).unwrap();
+    assert_eq!(rollback.last().unwrap(), "DELETE FROM seaql_expand_contract WHERE name = 'chef_contact_jsonb'");
+
+    let contract = CONTACT.statements(ChangePhase::Contract, MigrationDirection::Up).unwrap();
+    let rules: Vec<LintRule> = lint_statements(&contract).into_iter().map(|(rule, _)| rule).collect();
+    assert_eq!(rules, vec![LintRule::DropColumn]);
+    match CONTACT.statements(ChangePhase::Contract, MigrationDirection::Down) {
+      Err(DbErr::Migration(message)) => assert_eq!(message, "We Don't Do That Here"),
+      _ => panic!(),
+    }
+  }
+
+  #[test]
+  fn changes_formats() {
+    let states = vec![ChangeState {
+      name: String::from("chef_contact_jsonb"),
+      phase: ChangePhase::Backfill,
+      updated_at: Utc.timestamp_opt(1654128000, 0).single(),
+    }];
+
+    assert_eq!(
+      changes_table(&states),
+      "Change              Phase         Updated at\nchef_contact_jsonb  backfill      2022-06-02 00:00:00 UTC\n"
+    );
+    assert_eq!(changes_table(&[]), "No expand/contract changes started\n");
+  }
+}
@@ -0,0 +1,372 @@
+use std::env;
+use std::fmt;
+use std::fs;
+use std::time::Duration;
+
+use chrono::{DateTime, TimeZone, Utc};
+use sea_orm::{ConnectionTrait, DatabaseBackend, DatabaseConnection, DbErr, Statement};
+use sea_orm_migration::SchemaManager;
+use serde::{Serialize, Serializer};
+
+use super::report::MigrationReport;
+use super::{MigrationDirection, MigrationType};
+
+/// Table holding audit record of every migration run changing DB. It is kept by Fresh
+pub const HISTORY_TABLE: &str = "seaql_migration_history";
+
+/// Commit the binary was built from, set by build script
+const GIT_SHA: &str = env!("GIT_SHA");
+
+/// Outcome of migration run:
+/// * RunOutcome::Success - Every step was done
+/// * RunOutcome::Interrupted - Run stopped on shutdown request, steps done so far are kept
+/// * RunOutcome::Failed - Run failed, see error of the record
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RunOutcome {
+  Success,
+  Interrupted,
+  Failed,
+}
+
+impl RunOutcome {
+  fn from_string(value: &str) -> RunOutcome {
+    match value {
+      "success" => RunOutcome::Success,
+      "interrupted" => RunOutcome::Interrupted,
+      _ => RunOutcome::Failed,
+    }
+  }
+}
+
+impl fmt::Display for RunOutcome {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      RunOutcome::Success => write!(f, "success"),
+      RunOutcome::Interrupted => write!(f, "interrupted"),
+      RunOutcome::Failed => write!(f, "failed"),
+    }
+  }
+}
+
+/// Migration applied or rolled back by the run, recorded as "name (direction)"
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RunStep {
+  pub name: String,
+  pub direction: MigrationDirection,
+}
+
+impl RunStep {
+  fn from_string(value: &str) -> Result<RunStep, DbErr> {
+    let direction = match value.rsplit_once(" (") {
+      Some((name, "up)")) => Some((name, MigrationDirection::Up)),
+      Some((name, "down)")) => Some((name, MigrationDirection::Down)),
+      _ => None,
+    };
+    direction
+      .map(|(name, direction)| RunStep { name: name.to_string(), direction })
+      .ok_or_else(|| DbErr::Custom(format!("Unsupported migration step {} in history", value)))
+  }
+}
+
+impl fmt::Display for RunStep {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{} ({})", self.name, self.direction)
+  }
+}
+
+/// Audit record of single migration run: operation (e.g. "up" or "refresh"), migrations applied or rolled back
+/// in order, who ran it where, which build ran it, when, how long and with what outcome.
+/// Failed run keeps migrations committed before the failure
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MigrationRun {
+  pub operation: String,
+  pub migrations: Vec<RunStep>,
+  pub operator: String,
+  pub hostname: String,
+  pub app_version: String,
+  pub git_sha: String,
+  pub started_at: DateTime<Utc>,
+  #[serde(rename = "duration_ms", serialize_with = "serialize_millis")]
+  pub duration: Duration,
+  pub outcome: RunOutcome,
+  pub error: Option<String>,
+}
+
+impl MigrationRun {
+  /// Describe run of migration type with report of done steps and error it failed with, made by current OS user
+  /// on this host with this build
+  pub fn new(
+    migration_type: &MigrationType,
+    report: &MigrationReport,
+    error: Option<&DbErr>,
+    started_at: DateTime<Utc>,
+    duration: Duration,
+  ) -> MigrationRun {
+    let outcome = match error {
+      Some(_) => RunOutcome::Failed,
+      None if report.interrupted => RunOutcome::Interrupted,
+      None => RunOutcome::Success,
+    };
+
+    MigrationRun {
+      operation: migration_type.operation().to_string(),
+      migrations: report
+        .steps
+        .iter()
+        .map(|step| RunStep { name: step.name.clone(), direction: step.direction })
+        .collect(),
+      operator: operator(),
+      hostname: hostname(),
+      app_version: env!("CARGO_PKG_VERSION").to_string(),
+      git_sha: GIT_SHA.to_string(),
+      started_at,
+      duration,
+      outcome,
+      error: error.map(|e| e.to_string()),
+    }
+  }
+}
+
+fn serialize_millis<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+  serializer.serialize_u64(duration.as_millis() as u64)
+}
+
+/// OS user running the binary, read from USER, LOGNAME or USERNAME ENV
+fn operator() -> String {
+  ["USER", "LOGNAME", "USERNAME"]
+    .iter()
+    .find_map(|name| env::var(name).ok().filter(|user| !user.is_empty()))
+    .unwrap_or_else(|| String::from("unknown"))
+}
+
+/// Name of host running the binary, read from HOSTNAME ENV or /etc/hostname
+fn hostname() -> String {
+  env::var("HOSTNAME")
+    .ok()
+    .or_else(|| fs::read_to_string("/etc/hostname").ok())
+    .map(|hostname| hostname.trim().to_string())
+    .filter(|hostname| !hostname.is_empty())
+    .unwrap_or_else(|| String::from("unknown"))
+}
+
+/// Append run to history table, creating it if absent
+pub async fn record_run(connection: &DatabaseConnection, run: &MigrationRun) -> Result<(), DbErr> {
+  connection
+    .execute_unprepared(&format!(
+      "CREATE TABLE IF NOT EXISTS {} (id serial PRIMARY KEY, operation varchar NOT NULL, migrations text NOT NULL, \
+       operator varchar NOT NULL, hostname varchar NOT NULL, app_version varchar NOT NULL, git_sha varchar NOT NULL, \
+       started_at bigint NOT NULL, duration_ms bigint NOT NULL, outcome varchar NOT NULL, error text)",
+      HISTORY_TABLE
+    ))
+    .await?;
+
+  connection
+    .execute(Statement::from_sql_and_values(
+      DatabaseBackend::Postgres,
+      &format!(
+        "INSERT INTO {} (operation, migrations, operator, hostname, app_version, git_sha, started_at, duration_ms, \
+         outcome, error) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)",
+        HISTORY_TABLE
+      ),
+      [
+        run.operation.clone().into(),
+        run.migrations.iter().map(|step| step.to_string()).collect::<Vec<String>>().join(", ").into(),
+        run.operator.clone().into(),
+        run.hostname.clone().into(),
+        run.app_version.clone().into(),
+        run.git_sha.clone().into(),
+        run.started_at.timestamp().into(),
+        (run.duration.as_millis() as i64).into(),
+        run.outcome.to_string().into(),
+        run.error.clone().into(),
+      ],
+    ))
+    .await?;
+  Ok(())
+}
+
+/// Recorded runs, oldest first. Limit takes the latest runs only. Does not create history table
+pub async fn migration_history(
+  connection: &DatabaseConnection,
+  limit: Option<u32>,
+) -> Result<Vec<MigrationRun>, DbErr> {
+  if !SchemaManager::new(connection).has_table(HISTORY_TABLE).await? {
+    return Ok(Vec::new());
+  }
+
+  let limit = limit.map(|limit| format!(" LIMIT {}", limit)).unwrap_or_default();
+  let rows = connection
+    .query_all(Statement::from_string(
+      DatabaseBackend::Postgres,
+      format!(
+        "SELECT operation, migrations, operator, hostname, app_version, git_sha, started_at, duration_ms, outcome, \
+         error FROM {} ORDER BY id DESC{}",
+        HISTORY_TABLE, limit
+      ),
+    ))
+    .await?;
+
+  let mut runs = Vec::new();
+  for row in rows.iter().rev() {
+    let migrations: String = row.try_get("", "migrations")?;
+    runs.push(MigrationRun {
+      operation: row.try_get("", "operation")?,
+      migrations: migrations
+        .split(", ")
+        .filter(|step| !step.is_empty())
+        .map(RunStep::from_string)
+        .collect::<Result<Vec<RunStep>, DbErr>>()?,
+      operator: row.try_get("", "operator")?,
+      hostname: row.try_get("", "hostname")?,
+      app_version: row.try_get("", "app_version")?,
+      git_sha: row.try_get("", "git_sha")?,
+      started_at: Utc.timestamp_opt(row.try_get("", "started_at")?, 0).single().unwrap_or_default(),
+      duration: Duration::from_millis(row.try_get::<i64>("", "duration_ms")?.max(0) as u64),
+      outcome: RunOutcome::from_string(&row.try_get::<String>("", "outcome")?),
+      error: row.try_get("", "error")?,
+    });
+  }
+  Ok(runs)
+}
+
+/// Format runs as human-readable text, one line per run followed by error of failed one
+pub fn history_text(runs: &[MigrationRun]) -> String {
+  if runs.is_empty() {
+    return String::from("No migration runs recorded\n");
+  }
+
+  let mut text = String::new();
+  for run in runs {
+    let migrations = if run.migrations.is_empty() {
+      String::from("-")
+    } else {
+      run.migrations.iter().map(|step| step.to_string()).collect::<Vec<String>>().join(", ")
+    };
+    text.push_str(&format!(
+      "{}  {:<7}  {:<11}  {:>6} ms  {}@{}  {} ({})  {}\n",
+      run.started_at.format("%Y-%m-%d %H:%M:%S UTC"),
+      run.operation,
+      run.outcome.to_string(),
+      run.duration.as_millis(),
+      run.operator,
+      run.hostname,
+      run.app_version,
+      run.git_sha,
+      migrations
+    ));
+    if let Some(error) = &run.error {
+      text.push_str(&format!("  {}\n", error));
+    }
+  }
+  text
+}
+
+/// Format runs as JSON array
+pub fn history_json(runs: &[MigrationRun]) -> String {
+  serde_json::to_string_pretty(runs).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod history_test {
+  use super::{history_json, history_text, migration_history, MigrationRun, RunOutcome, RunStep};
+  use crate::connector::db::{MigrationDirection, MigrationReport, MigrationStep, MigrationType};
+  use chrono::{TimeZone, Utc};
+  use futures::executor::block_on;
+  use sea_orm::{DatabaseBackend, DbErr, MockDatabase, Value};
+  use std::collections::BTreeMap;
+  use std::time::Duration;
+
+  fn run(outcome: RunOutcome, error: Option<&str>) -> MigrationRun {
+    MigrationRun {
+      operation: String::from("up"),
+      migrations: vec![
+        RunStep { name: String::from("m_1"), direction: MigrationDirection::Up },
+        RunStep { name: String::from("m_2"), direction: MigrationDirection::Up },
+      ],
+      operator: String::from("deploy"),
+      hostname: String::from("ci"),
+      app_version: String::from("0.1.0"),
+      git_sha: String::from("abc1234"),
+      started_at: Utc.timestamp_opt(1654128000, 0).single().unwrap(),
+      duration: Duration::from_millis(42),
+      outcome,
+      error: error.map(String::from),
+    }
+  }
+
+  #[test]
+  fn run_of_result() {
+    let report = MigrationReport {
+      steps: vec![MigrationStep {
+        name: String::from("m_1"),
+        direction: MigrationDirection::Down,
+        duration: Duration::from_millis(3),
+      }],
+      version: None,
+      interrupted: true,
+    };
+    let started_at = Utc::now();
+
+    let interrupted = MigrationRun::new(&MigrationType::Down(Some(2)), &report, None, started_at, Duration::ZERO);
+    assert_eq!(interrupted.operation, "down");
+    assert_eq!(
+      interrupted.migrations,
+      vec![RunStep { name: String::from("m_1"), direction: MigrationDirection::Down }]
+    );
+    assert_eq!(interrupted.outcome, RunOutcome::Interrupted);
+
+    let error = DbErr::Custom(String::from("boom"));
+    let failed = MigrationRun::new(&MigrationType::Refresh, &report, Some(&error), started_at, Duration::ZERO);
+    assert_eq!(failed.operation, "refresh");
+    assert_eq!(failed.migrations.len(), 1);
+    assert_eq!(failed.outcome, RunOutcome::Failed);
+    assert_eq!(failed.error, Some(String::from("Custom Error: boom")));
+  }
+
+  #[test]
+  fn history_formats() {
+    let runs = vec![run(RunOutcome::Success, None), run(RunOutcome::Failed, Some("Custom Error: boom"))];
+
+    assert_eq!(
+      history_text(&runs),
+      "2022-06-02 00:00:00 UTC  up       success          42 ms  deploy@ci  0.1.0 (abc1234)  m_1 (up), m_2 (up)\n\
+       2022-06-02 00:00:00 UTC  up       failed           42 ms  deploy@ci  0.1.0 (abc1234)  m_1 (up), m_2 \
+       (up)\n  Custom Error: boom\n"
+    );
+    assert_eq!(history_text(&[]), "No migration runs recorded\n");
+    assert!(history_json(&runs).contains("\"duration_ms\": 42"));
+  }
+
+  #[test]
+  fn history_from_db() {
+    let connection = MockDatabase::new(DatabaseBackend::Postgres)
+      .append_query_results([[BTreeMap::from([("has_table", Value::Bool(Some(true)))])]])
+      .append_query_results([[BTreeMap::from([
+        ("operation", Value::from("refresh")),
+        ("migrations", Value::from("m_1 (down), m_1 (up)")),
+        ("operator", Value::from("deploy")),
+        ("hostname", Value::from("ci")),
+        ("app_version", Value::from("0.1.0")),
+        ("git_sha", Value::from("abc1234")),
+        ("started_at", Value::BigInt(Some(1654128000))),
+        ("duration_ms", Value::BigInt(Some(42))),
+        ("outcome", Value::from("success")),
+        ("error", Value::String(None)),
+      ])]])
+      .into_connection();
+
+    let runs = block_on(migration_history(&connection, Some(5))).unwrap();
+
+    assert_eq!(runs.len(), 1);
+    assert_eq!(
+      runs[0].migrations,
+      vec![
+        RunStep { name: String::from("m_1"), direction: MigrationDirection::Down },
+        RunStep { name: String::from("m_1"), direction: MigrationDirection::Up },
+      ]
+    );
+    assert_eq!(runs[0].outcome, RunOutcome::Success);
+    assert!(format!("{:?}", connection.into_transaction_log()).contains("ORDER BY id DESC LIMIT 5"));
+  }
+}
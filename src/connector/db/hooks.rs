@@ -0,0 +1,89 @@
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use sea_orm::{ConnectionTrait, DbErr};
+
+/// Moment of migration run hook is called at:
+/// * HookEvent::BeforeAll - Before the first migration of the run, inside its transaction if any
+/// * HookEvent::AfterEach - After every migration applied or rolled back, inside its transaction if any
+/// * HookEvent::AfterAll - After migration transactions are committed
+/// * HookEvent::OnFailure - After transaction of failed migration is rolled back
+///
+/// Hooks are not called if there is nothing to migrate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookEvent {
+  BeforeAll,
+  AfterEach,
+  AfterAll,
+  OnFailure,
+}
+
+/// Future returned by hook
+pub type HookFuture<'a> = Pin<Box<dyn Future<Output = Result<(), DbErr>> + Send + 'a>>;
+
+/// Hook receives connection and name of migration: the first one for BeforeAll, the last one for AfterAll,
+/// the failed one for OnFailure
+pub type Hook = Arc<dyn for<'a> Fn(&'a dyn ConnectionTrait, &'a str) -> HookFuture<'a> + Send + Sync>;
+
+/// Uses to hold hooks registered on `Migration`, see `Migration::with_hook`
+#[derive(Clone, Default)]
+pub struct Hooks {
+  hooks: Vec<(HookEvent, Hook)>,
+}
+
+impl Hooks {
+  /// Register hook. Hooks of the same event are called in order of registration
+  pub fn add(&mut self, event: HookEvent, hook: Hook) {
+    self.hooks.push((event, hook));
+  }
+
+  /// Call every hook registered for event. Stops at the first failed hook
+  pub async fn run(&self, event: HookEvent, connection: &dyn ConnectionTrait, name: &str) -> Result<(), DbErr> {
+    for (_, hook) in self.hooks.iter().filter(|(hook_event, _)| *hook_event == event) {
+      hook(connection, name).await?;
+    }
+    Ok(())
+  }
+}
+
+impl fmt::Debug for Hooks {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_list().entries(self.hooks.iter().map(|(event, _)| event)).finish()
+  }
+}
+
+#[cfg(test)]
+mod hooks_test {
+  use super::{HookEvent, Hooks};
+  use futures::executor::block_on;
+  use sea_orm::{DatabaseBackend, DbErr, MockDatabase, MockExecResult};
+  use std::sync::Arc;
+
+  #[test]
+  fn run_hooks_of_event() {
+    let connection = MockDatabase::new(DatabaseBackend::Postgres)
+      .append_exec_results(vec![MockExecResult::default(); 2])
+      .into_connection();
+    let mut hooks = Hooks::default();
+    hooks.add(
+      HookEvent::AfterEach,
+      Arc::new(|connection, name| {
+        Box::pin(async move {
+          connection.execute_unprepared(&format!("NOTIFY migrated, '{}'", name)).await?;
+          Ok(())
+        })
+      }),
+    );
+    hooks.add(HookEvent::OnFailure, Arc::new(|_, _| Box::pin(async { Err(DbErr::Custom(String::from("failed"))) })));
+
+    assert!(block_on(hooks.run(HookEvent::AfterEach, &connection, "m_1")).is_ok());
+    assert!(block_on(hooks.run(HookEvent::AfterAll, &connection, "m_1")).is_ok());
+    assert!(block_on(hooks.run(HookEvent::OnFailure, &connection, "m_1")).is_err());
+    assert_eq!(format!("{:?}", hooks), "[AfterEach, OnFailure]");
+
+    let log = format!("{:?}", connection.into_transaction_log());
+    assert!(log.contains("NOTIFY migrated, 'm_1'"));
+  }
+}
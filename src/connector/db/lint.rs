@@ -0,0 +1,277 @@
+use std::fmt;
+
+use log::warn;
+use sea_orm::{DatabaseConnection, DbErr};
+use sea_orm_migration::{MigrationTrait, MigratorTrait};
+use serde::Serialize;
+
+use super::dry_run::{applied_versions, record_migration};
+use super::sql_migration::split_statements;
+use super::MigrationDirection;
+use crate::migrator::Migrator;
+
+/// Error message of default `MigrationTrait::down`
+pub const MISSING_DOWN: &str = "We Don't Do That Here";
+
+/// Risky operation found by lint:
+/// * LintRule::DropTable - Table is dropped with its data
+/// * LintRule::DropColumn - Column is dropped with its data
+/// * LintRule::NotNullWithoutDefault - NOT NULL column is added or set without default, failing on existing rows
+/// * LintRule::NonConcurrentIndex - Index is created without CONCURRENTLY, blocking writes to existing table
+/// * LintRule::TableRewrite - Column type is changed, rewriting the table under exclusive lock
+/// * LintRule::MissingDown - Migration does not implement `down`, so it cannot be rolled back
+///
+/// Tables created by the same migration are not checked for NOT NULL, index and type changes, as they are empty.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LintRule {
+  DropTable,
+  DropColumn,
+  NotNullWithoutDefault,
+  NonConcurrentIndex,
+  TableRewrite,
+  MissingDown,
+}
+
+impl fmt::Display for LintRule {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      LintRule::DropTable => write!(f, "drops table"),
+      LintRule::DropColumn => write!(f, "drops column"),
+      LintRule::NotNullWithoutDefault => write!(f, "adds NOT NULL without default"),
+      LintRule::NonConcurrentIndex => write!(f, "creates index without CONCURRENTLY"),
+      LintRule::TableRewrite => write!(f, "changes column type, rewriting table"),
+      LintRule::MissingDown => write!(f, "does not implement down"),
+    }
+  }
+}
+
+/// Risky operation of single migration. Statement is None for missing down
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LintWarning {
+  pub migration: String,
+  pub rule: LintRule,
+  pub statement: Option<String>,
+}
+
+impl fmt::Display for LintWarning {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match &self.statement {
+      Some(statement) => write!(f, "{}: {}: {}", self.migration, self.rule, statement),
+      None => write!(f, "{}: {}", self.migration, self.rule),
+    }
+  }
+}
+
+/// Lint every migration of `Migrator::migrations()` which is not applied to DB
+pub async fn lint_pending(connection: &DatabaseConnection) -> Result<Vec<LintWarning>, DbErr> {
+  let applied = applied_versions(connection).await?;
+
+  let mut warnings = Vec::new();
+  for migration in
+    Migrator::migrations().iter().filter(|migration| !applied.iter().any(|name| name == migration.name()))
+  {
+    warnings.extend(lint_migration(migration.as_ref()).await);
+  }
+  Ok(warnings)
+}
+
+/// Lint SQL recorded from migration's up, see `record_migration`, and check it implements down.
+/// SQL scripts of `SqlMigration` are split into statements
+/// Migrations which cannot be recorded are skipped
+pub async fn lint_migration(migration: &dyn MigrationTrait) -> Vec<LintWarning> {
+  let name = migration.name().to_string();
+  let warning = |rule: LintRule, statement: Option<String>| LintWarning { migration: name.clone(), rule, statement };
+
+  let mut warnings: Vec<LintWarning> = match record_migration(migration, MigrationDirection::Up).await {
+    Ok(statements) => {
+      let statements: Vec<String> = statements.iter().flat_map(|sql| split_statements(sql)).collect();
+      lint_statements(&statements).into_iter().map(|(rule, sql)| warning(rule, Some(sql))).collect()
+    },
+    Err(e) => {
+      warn!("Migration {} is not linted: {}", name, e);
+      Vec::new()
+    },
+  };
+  if let Err(DbErr::Migration(message)) = record_migration(migration, MigrationDirection::Down).await {
+    if message == MISSING_DOWN {
+      warnings.push(warning(LintRule::MissingDown, None));
+    }
+  }
+  warnings
+}
+
+/// Risky operations of SQL statements run in order by single migration
+pub fn lint_statements(statements: &[String]) -> Vec<(LintRule, String)> {
+  let mut created: Vec<String> = Vec::new();
+  let mut found = Vec::new();
+  for statement in statements {
+    let sql = statement.split_whitespace().collect::<Vec<&str>>().join(" ").to_uppercase();
+    let mut flag = |rule: LintRule| found.push((rule, statement.clone()));
+
+    if sql.starts_with("CREATE TABLE ") {
+      created.extend(table_after(statement, "TABLE"));
+    } else if sql.starts_with("DROP TABLE ") {
+      flag(LintRule::DropTable);
+    } else if sql.starts_with("CREATE INDEX ") || sql.starts_with("CREATE UNIQUE INDEX ") {
+      let is_new = table_after(statement, "ON").is_some_and(|table| created.contains(&table));
+      if !sql.contains(" CONCURRENTLY ") && !is_new {
+        flag(LintRule::NonConcurrentIndex);
+      }
+    } else if sql.starts_with("ALTER TABLE ") {
+      let is_new = table_after(statement, "TABLE").is_some_and(|table| created.contains(&table));
+      let clauses = clauses(&sql);
+      if clauses.iter().any(|clause| clause.starts_with("DROP COLUMN ")) {
+        flag(LintRule::DropColumn);
+      }
+      if is_new {
+        continue;
+      }
+      let adds_not_null = clauses
+        .iter()
+        .any(|clause| clause.starts_with("ADD ") && clause.contains(" NOT NULL") && !clause.contains(" DEFAULT "));
+      let sets_not_null = clauses.iter().any(|clause| clause.ends_with(" SET NOT NULL"))
+        && !clauses.iter().any(|clause| clause.contains(" SET DEFAULT "));
+      if adds_not_null || sets_not_null {
+        flag(LintRule::NotNullWithoutDefault);
+      }
+      if clauses
+        .iter()
+        .any(|clause| clause.starts_with("ALTER ") && (clause.contains(" TYPE ") || clause.contains(" SET DATA TYPE ")))
+      {
+        flag(LintRule::TableRewrite);
+      }
+    }
+  }
+  found
+}
+
+/// Clauses of ALTER TABLE statement, split at commas outside of parentheses and quotes, without the table part
+fn clauses(sql: &str) -> Vec<String> {
+  let mut clauses = Vec::new();
+  let mut depth = 0;
+  let mut quote: Option<char> = None;
+  let mut clause = String::new();
+  for c in sql.chars() {
+    match (quote, c) {
+      (Some(q), c) if c == q => quote = None,
+      (Some(_), _) => {},
+      (None, '"') | (None, '\'') => quote = Some(c),
+      (None, '(') => depth += 1,
+      (None, ')') => depth -= 1,
+      (None, ',') if depth == 0 => {
+        clauses.push(clause.trim().to_string());
+        clause.clear();
+        continue;
+      },
+      _ => {},
+    }
+    clause.push(c);
+  }
+  clauses.push(clause.trim().to_string());
+
+  // The first clause starts with "ALTER TABLE <name> "
+  if let Some(first) = clauses.first_mut() {
+    let words: Vec<&str> = first.split_whitespace().collect();
+    let skip = words.iter().skip(2).take_while(|word| ["IF", "EXISTS", "ONLY"].contains(word)).count() + 3;
+    *first = words.iter().skip(skip).copied().collect::<Vec<&str>>().join(" ");
+  }
+  clauses
+}
+
+/// Name of table following the keyword, without quotes and schema, e.g. "chef" of `ALTER TABLE "public"."chef"`
+fn table_after(statement: &str, keyword: &str) -> Option<String> {
+  let mut words = statement.split_whitespace().skip_while(|word| !word.eq_ignore_ascii_case(keyword)).skip(1);
+  let name = words.find(|word| !["IF", "NOT", "EXISTS", "ONLY"].contains(&word.to_uppercase().as_str()))?;
+  let name = name.rsplit('.').next()?.trim_matches(|c| c == '"' || c == '(');
+  Some(name.to_string())
+}
+
+/// Format warnings as human-readable text, one line per warning
+pub fn lint_text(warnings: &[LintWarning]) -> String {
+  if warnings.is_empty() {
+    return String::from("No risky operations found\n");
+  }
+  warnings.iter().map(|warning| format!("{}\n", warning)).collect()
+}
+
+#[cfg(test)]
+mod lint_test {
+  use super::{lint_migration, lint_statements, lint_text, LintRule, LintWarning};
+  use crate::migrator::Migrator;
+  use futures::executor::block_on;
+  use sea_orm_migration::prelude::*;
+
+  struct NoDown;
+
+  impl MigrationName for NoDown {
+    fn name(&self) -> &str {
+      "m_3"
+    }
+  }
+
+  #[async_trait::async_trait]
+  impl MigrationTrait for NoDown {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+      manager.drop_table(Table::drop().table(Alias::new("chef")).to_owned()).await
+    }
+  }
+
+  fn rules(statements: &[&str]) -> Vec<LintRule> {
+    let statements: Vec<String> = statements.iter().map(|statement| statement.to_string()).collect();
+    lint_statements(&statements).into_iter().map(|(rule, _)| rule).collect()
+  }
+
+  #[test]
+  fn lint_risky_statements() {
+    assert_eq!(
+      rules(&[
+        "ALTER TABLE \"chef\" DROP COLUMN \"contact_details\"",
+        "ALTER TABLE \"chef\" ADD COLUMN \"rating\" integer NOT NULL",
+        "ALTER TABLE \"chef\" ALTER COLUMN \"name\" TYPE text, ALTER COLUMN \"name\" SET NOT NULL",
+        "CREATE INDEX \"idx-chef-name\" ON \"chef\" (\"name\")",
+        "DROP TABLE \"bakery\"",
+      ]),
+      vec![
+        LintRule::DropColumn,
+        LintRule::NotNullWithoutDefault,
+        LintRule::NotNullWithoutDefault,
+        LintRule::TableRewrite,
+        LintRule::NonConcurrentIndex,
+        LintRule::DropTable,
+      ]
+    );
+  }
+
+  #[test]
+  fn lint_safe_statements() {
+    assert!(rules(&[
+      "ALTER TABLE \"chef\" ADD COLUMN \"rating\" integer NOT NULL DEFAULT 0",
+      "ALTER TABLE \"chef\" ADD COLUMN \"nickname\" varchar",
+      "CREATE INDEX CONCURRENTLY \"idx-chef-name\" ON \"chef\" (\"name\")",
+      "CREATE TABLE \"oven\" ( \"id\" serial NOT NULL PRIMARY KEY, \"bakery_id\" integer NOT NULL )",
+      "CREATE INDEX \"idx-oven-bakery_id\" ON \"oven\" (\"bakery_id\")",
+      "ALTER TABLE \"oven\" ADD COLUMN \"name\" varchar NOT NULL",
+    ])
+    .is_empty());
+  }
+
+  #[test]
+  fn lint_default_migrations() {
+    for migration in Migrator::migrations() {
+      assert_eq!(block_on(lint_migration(migration.as_ref())), vec![]);
+    }
+  }
+
+  #[test]
+  fn lint_missing_down() {
+    let warnings = block_on(lint_migration(&NoDown));
+
+    assert_eq!(
+      warnings.iter().map(|warning| warning.rule).collect::<Vec<_>>(),
+      vec![LintRule::DropTable, LintRule::MissingDown]
+    );
+    assert_eq!(lint_text(&warnings), "m_3: drops table: DROP TABLE \"chef\"\nm_3: does not implement down\n");
+    assert_eq!(lint_text(&Vec::<LintWarning>::new()), "No risky operations found\n");
+  }
+}
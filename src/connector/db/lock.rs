@@ -0,0 +1,92 @@
+use std::time::Duration;
+
+use log::info;
+use sea_orm::{ConnectionTrait, DatabaseConnection, DatabaseTransaction, DbErr, TransactionTrait};
+
+/// Key of Postgres advisory lock taken by migration runners. Advisory locks are scoped to the database,
+/// so runners of different databases on the same server do not block each other
+pub const MIGRATION_LOCK_KEY: i64 = 0x5ea0_0001;
+
+/// Advisory lock serializing migration runs. Lock is held by transaction pinned to single pooled connection,
+/// so migrations themselves run on other connections of the pool. Lock is released when transaction ends,
+/// including when the connection is lost
+pub struct MigrationLock {
+  transaction: DatabaseTransaction,
+}
+
+impl MigrationLock {
+  /// SQL statements taking the lock, waiting for it no longer than timeout. Zero timeout waits forever
+  pub fn statements(timeout: Duration) -> Vec<String> {
+    vec![
+      format!("SET LOCAL lock_timeout = '{}ms'", timeout.as_millis()),
+      format!("SELECT pg_advisory_xact_lock({})", MIGRATION_LOCK_KEY),
+    ]
+  }
+
+  /// Wait until lock held by another runner is released and take it
+  pub async fn acquire(connection: &DatabaseConnection, timeout: Duration) -> Result<MigrationLock, DbErr> {
+    let transaction = connection.begin().await?;
+    for statement in MigrationLock::statements(timeout) {
+      if let Err(e) = transaction.execute_unprepared(&statement).await {
+        transaction.rollback().await?;
+        return Err(DbErr::Custom(format!(
+          "Migration lock is not acquired within {} seconds: {}",
+          timeout.as_secs(),
+          e
+        )));
+      }
+    }
+    info!("Migration lock acquired");
+
+    Ok(MigrationLock { transaction })
+  }
+
+  /// Release the lock
+  pub async fn release(self) -> Result<(), DbErr> {
+    self.transaction.commit().await?;
+    info!("Migration lock released");
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod lock_test {
+  use super::MigrationLock;
+  use futures::executor::block_on;
+  use sea_orm::{DatabaseBackend, DbErr, MockDatabase, MockExecResult};
+  use std::time::Duration;
+
+  #[test]
+  fn statements_with_timeout() {
+    assert_eq!(
+      MigrationLock::statements(Duration::from_secs(5)),
+      vec![String::from("SET LOCAL lock_timeout = '5000ms'"), String::from("SELECT pg_advisory_xact_lock(1587544065)")]
+    );
+  }
+
+  #[test]
+  fn acquire_and_release() {
+    let connection = MockDatabase::new(DatabaseBackend::Postgres)
+      .append_exec_results(vec![MockExecResult::default(); 2])
+      .into_connection();
+
+    let lock = block_on(MigrationLock::acquire(&connection, Duration::from_secs(1))).unwrap();
+    assert!(block_on(lock.release()).is_ok());
+
+    let log = format!("{:?}", connection.into_transaction_log());
+    assert!(log.contains("pg_advisory_xact_lock"));
+  }
+
+  #[test]
+  fn acquire_timed_out() {
+    let connection = MockDatabase::new(DatabaseBackend::Postgres)
+      .append_exec_results(vec![MockExecResult::default()])
+      .append_exec_errors(vec![DbErr::Custom(String::from("canceling statement due to lock timeout"))])
+      .into_connection();
+
+    match block_on(MigrationLock::acquire(&connection, Duration::from_secs(1))) {
+      Err(e) => assert!(e.to_string().contains("Migration lock is not acquired within 1 seconds")),
+      Ok(_) => panic!(),
+    }
+  }
+}
@@ -0,0 +1,133 @@
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbErr};
+
+use super::DbConfig;
+
+/// Uses to create the runtime role and grant it DML on the migrated schema. Must be run with migration
+/// credentials. Initialize by running:
+/// ```
+/// use rust_orm_template::connector::db::{DbConfig, Provision};
+///
+/// if let Ok(config) = DbConfig::from_env() {
+///   let provision = Provision::new(&config);
+/// }
+/// ```
+#[derive(Debug, PartialEq, Eq)]
+pub struct Provision {
+  role: String,
+  password: String,
+  database: String,
+  schema: String,
+}
+
+impl Provision {
+  /// Uses to initialize provisioning of the runtime role described in config
+  #[allow(dead_code)]
+  pub fn new(config: &DbConfig) -> Provision {
+    Provision {
+      role: config.db_user.clone(),
+      password: config.db_password.clone(),
+      database: config.db_name.clone(),
+      schema: config.db_schema.clone(),
+    }
+  }
+
+  /// Statements to run. All of them are idempotent, so provisioning may be repeated after every migration.
+  /// Default privileges of PUBLIC on the database and schema are revoked, so the runtime role cannot run DDL
+  /// through them, e.g. create tables in "public" schema of PostgreSQL 14 or older
+  pub fn statements(&self) -> Vec<String> {
+    let role = quote_ident(&self.role);
+    let database = quote_ident(&self.database);
+    let schema = quote_ident(&self.schema);
+
+    vec![
+      format!(
+        "DO $provision$ BEGIN IF NOT EXISTS (SELECT FROM pg_catalog.pg_roles WHERE rolname = {}) THEN CREATE ROLE {}; END IF; END $provision$",
+        quote_literal(&self.role),
+        role
+      ),
+      format!(
+        "ALTER ROLE {} WITH LOGIN NOSUPERUSER NOCREATEDB NOCREATEROLE PASSWORD {}",
+        role,
+        quote_literal(&self.password)
+      ),
+      format!("REVOKE ALL ON DATABASE {} FROM PUBLIC", database),
+      format!("REVOKE CREATE ON SCHEMA {} FROM PUBLIC", schema),
+      format!("GRANT CONNECT ON DATABASE {} TO {}", database, role),
+      format!("GRANT USAGE ON SCHEMA {} TO {}", schema, role),
+      format!("GRANT SELECT, INSERT, UPDATE, DELETE ON ALL TABLES IN SCHEMA {} TO {}", schema, role),
+      format!("GRANT USAGE, SELECT ON ALL SEQUENCES IN SCHEMA {} TO {}", schema, role),
+      format!("ALTER DEFAULT PRIVILEGES IN SCHEMA {} GRANT SELECT, INSERT, UPDATE, DELETE ON TABLES TO {}", schema, role),
+      format!("ALTER DEFAULT PRIVILEGES IN SCHEMA {} GRANT USAGE, SELECT ON SEQUENCES TO {}", schema, role),
+    ]
+  }
+
+  /// Run provisioning on DB
+  #[allow(dead_code)]
+  pub async fn run(&self, connection: &DatabaseConnection) -> Result<&Self, DbErr> {
+    for statement in self.statements() {
+      connection.execute_unprepared(&statement).await?;
+    }
+
+    Ok(self)
+  }
+}
+
+/// Quotes SQL identifier, e.g. role or schema name
+pub(crate) fn quote_ident(value: &str) -> String {
+  format!("\"{}\"", value.replace('"', "\"\""))
+}
+
+/// Quotes SQL string literal
+pub(crate) fn quote_literal(value: &str) -> String {
+  format!("'{}'", value.replace('\'', "''"))
+}
+
+#[cfg(test)]
+mod provision_test {
+  use super::{quote_ident, quote_literal, Provision};
+
+  fn provision() -> Provision {
+    Provision {
+      role: String::from("app"),
+      password: String::from("pa'ss"),
+      database: String::from("test"),
+      schema: String::from("public"),
+    }
+  }
+
+  #[test]
+  fn quote_ident_escapes_quotes() {
+    assert_eq!(quote_ident("a\"b"), "\"a\"\"b\"");
+  }
+
+  #[test]
+  fn quote_literal_escapes_quotes() {
+    assert_eq!(quote_literal("a'b"), "'a''b'");
+  }
+
+  #[test]
+  fn statements_set_password() {
+    let statements = provision().statements();
+    assert!(statements
+      .contains(&String::from("ALTER ROLE \"app\" WITH LOGIN NOSUPERUSER NOCREATEDB NOCREATEROLE PASSWORD 'pa''ss'")));
+  }
+
+  #[test]
+  fn statements_grant_dml_only() {
+    let statements = provision().statements();
+    assert!(statements
+      .contains(&String::from("GRANT SELECT, INSERT, UPDATE, DELETE ON ALL TABLES IN SCHEMA \"public\" TO \"app\"")));
+    assert!(statements.iter().all(|s| !s.contains("GRANT CREATE") && !s.contains("GRANT ALL")));
+  }
+
+  #[test]
+  fn statements_revoke_public_privileges() {
+    let statements = provision().statements();
+    let revoke_database = statements.iter().position(|s| s == "REVOKE ALL ON DATABASE \"test\" FROM PUBLIC");
+    let revoke_schema = statements.iter().position(|s| s == "REVOKE CREATE ON SCHEMA \"public\" FROM PUBLIC");
+    let grant_connect = statements.iter().position(|s| s == "GRANT CONNECT ON DATABASE \"test\" TO \"app\"");
+
+    assert!(revoke_database.is_some() && revoke_schema.is_some());
+    assert!(revoke_database < grant_connect);
+  }
+}
@@ -0,0 +1,211 @@
+use std::future::Future;
+use std::pin::Pin;
+
+use sea_orm::prelude::async_trait;
+use sea_orm::{
+  AccessMode, ConnectionTrait, DatabaseTransaction, DbBackend, DbErr, ExecResult, IsolationLevel, QueryResult,
+  QueryStream, Statement, StreamTrait, TransactionError, TransactionTrait,
+};
+
+use super::Db;
+
+/// Statements allowed on read-only connection
+const READ_STATEMENTS: [&str; 6] = ["SELECT", "SHOW", "EXPLAIN", "WITH", "VALUES", "TABLE"];
+
+/// Keywords making read statement a write one, e.g. "WITH ... DELETE", "EXPLAIN ANALYZE INSERT" or "SELECT INTO"
+const WRITE_KEYWORDS: [&str; 8] = ["INSERT", "UPDATE", "DELETE", "MERGE", "INTO", "ANALYZE", "NEXTVAL", "SETVAL"];
+
+/// Returns true if SQL statement cannot modify data. Quoted literals and identifiers are not inspected
+pub fn is_read_statement(sql: &str) -> bool {
+  let mut unquoted = String::with_capacity(sql.len());
+  let mut quote: Option<char> = None;
+  for c in sql.chars() {
+    match quote {
+      Some(q) if c == q => quote = None,
+      Some(_) => {},
+      None if c == '\'' || c == '"' => {
+        quote = Some(c);
+        unquoted.push(' ');
+      },
+      None => unquoted.push(c),
+    }
+  }
+
+  let words: Vec<String> = unquoted
+    .split(|c: char| !c.is_alphanumeric() && c != '_')
+    .filter(|word| !word.is_empty())
+    .map(|word| word.to_uppercase())
+    .collect();
+
+  match words.first() {
+    Some(first) if READ_STATEMENTS.contains(&first.as_str()) => {
+      words.iter().all(|word| !WRITE_KEYWORDS.contains(&word.as_str()))
+    },
+    _ => false,
+  }
+}
+
+impl Db {
+  /// Returns true if DB is configured as read-only (POSTGRES_READ_ONLY)
+  pub fn is_read_only(&self) -> bool {
+    self.config.db_read_only
+  }
+
+  /// Returns error if DB is read-only and SQL statement may modify data
+  fn guard(&self, sql: &str) -> Result<(), DbErr> {
+    if self.is_read_only() && !is_read_statement(sql) {
+      return Err(DbErr::Custom(format!("Write operation rejected on read-only DB connection: {}", sql)));
+    }
+
+    Ok(())
+  }
+}
+
+/// Db may be used as connection, e.g. `bakery::Entity::find().all(&db)`. Writes are rejected before
+/// they reach the server if DB is read-only.
+#[async_trait::async_trait]
+impl ConnectionTrait for Db {
+  fn get_database_backend(&self) -> DbBackend {
+    self.connection.get_database_backend()
+  }
+
+  async fn execute(&self, stmt: Statement) -> Result<ExecResult, DbErr> {
+    self.guard(&stmt.sql)?;
+    self.connection.execute(stmt).await
+  }
+
+  async fn execute_unprepared(&self, sql: &str) -> Result<ExecResult, DbErr> {
+    self.guard(sql)?;
+    self.connection.execute_unprepared(sql).await
+  }
+
+  async fn query_one(&self, stmt: Statement) -> Result<Option<QueryResult>, DbErr> {
+    self.guard(&stmt.sql)?;
+    self.connection.query_one(stmt).await
+  }
+
+  async fn query_all(&self, stmt: Statement) -> Result<Vec<QueryResult>, DbErr> {
+    self.guard(&stmt.sql)?;
+    self.connection.query_all(stmt).await
+  }
+
+  fn support_returning(&self) -> bool {
+    self.connection.support_returning()
+  }
+
+  fn is_mock_connection(&self) -> bool {
+    self.connection.is_mock_connection()
+  }
+}
+
+/// Streams are guarded the same way as queries
+impl StreamTrait for Db {
+  type Stream<'a> = QueryStream;
+
+  fn stream<'a>(
+    &'a self,
+    stmt: Statement,
+  ) -> Pin<Box<dyn Future<Output = Result<Self::Stream<'a>, DbErr>> + 'a + Send>> {
+    Box::pin(async move {
+      self.guard(&stmt.sql)?;
+      self.connection.stream(stmt).await
+    })
+  }
+}
+
+/// Transactions of read-only DB are always started as `READ ONLY`: statements inside the transaction
+/// do not pass through Db, so writes are rejected by the server instead.
+#[async_trait::async_trait]
+impl TransactionTrait for Db {
+  async fn begin(&self) -> Result<DatabaseTransaction, DbErr> {
+    self.begin_with_config(None, None).await
+  }
+
+  async fn begin_with_config(
+    &self,
+    isolation_level: Option<IsolationLevel>,
+    access_mode: Option<AccessMode>,
+  ) -> Result<DatabaseTransaction, DbErr> {
+    let access_mode = match access_mode {
+      Some(AccessMode::ReadWrite) if self.is_read_only() => {
+        return Err(DbErr::Custom("Read-write transaction rejected on read-only DB connection".to_owned()));
+      },
+      _ if self.is_read_only() => Some(AccessMode::ReadOnly),
+      access_mode => access_mode,
+    };
+
+    self.connection.begin_with_config(isolation_level, access_mode).await
+  }
+
+  async fn transaction<F, T, E>(&self, callback: F) -> Result<T, TransactionError<E>>
+  where
+    F: for<'c> FnOnce(&'c DatabaseTransaction) -> Pin<Box<dyn Future<Output = Result<T, E>> + Send + 'c>> + Send,
+    T: Send,
+    E: std::error::Error + Send,
+  {
+    self.transaction_with_config(callback, None, None).await
+  }
+
+  async fn transaction_with_config<F, T, E>(
+    &self,
+    callback: F,
+    isolation_level: Option<IsolationLevel>,
+    access_mode: Option<AccessMode>,
+  ) -> Result<T, TransactionError<E>>
+  where
+    F: for<'c> FnOnce(&'c DatabaseTransaction) -> Pin<Box<dyn Future<Output = Result<T, E>> + Send + 'c>> + Send,
+    T: Send,
+    E: std::error::Error + Send,
+  {
+    let transaction =
+      self.begin_with_config(isolation_level, access_mode).await.map_err(TransactionError::Connection)?;
+
+    match callback(&transaction).await {
+      Ok(result) => {
+        transaction.commit().await.map_err(TransactionError::Connection)?;
+        Ok(result)
+      },
+      Err(err) => {
+        transaction.rollback().await.map_err(TransactionError::Connection)?;
+        Err(TransactionError::Transaction(err))
+      },
+    }
+  }
+}
+
+#[cfg(test)]
+mod read_only_test {
+  use super::is_read_statement;
+
+  #[test]
+  fn select_is_read() {
+    assert!(is_read_statement("SELECT \"bakery\".\"id\" FROM \"bakery\" WHERE \"name\" = $1"));
+  }
+
+  #[test]
+  fn quoted_keywords_are_ignored() {
+    assert!(is_read_statement("select * from \"update\" where name = 'delete'"));
+  }
+
+  #[test]
+  fn insert_is_write() {
+    assert!(!is_read_statement("INSERT INTO \"bakery\" (\"name\") VALUES ($1) RETURNING \"id\""));
+  }
+
+  #[test]
+  fn ddl_is_write() {
+    assert!(!is_read_statement("CREATE TABLE foo (id int)"));
+    assert!(!is_read_statement("SET default_transaction_read_only = off"));
+  }
+
+  #[test]
+  fn with_delete_is_write() {
+    assert!(!is_read_statement("WITH gone AS (DELETE FROM chef RETURNING id) SELECT * FROM gone"));
+  }
+
+  #[test]
+  fn explain_analyze_is_write() {
+    assert!(is_read_statement("EXPLAIN SELECT 1"));
+    assert!(!is_read_statement("EXPLAIN ANALYZE UPDATE chef SET name = $1"));
+  }
+}
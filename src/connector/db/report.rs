@@ -0,0 +1,97 @@
+use std::time::Duration;
+
+use serde::{Serialize, Serializer};
+
+use super::MigrationDirection;
+
+/// Single migration applied or rolled back during the run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MigrationStep {
+  pub name: String,
+  pub direction: MigrationDirection,
+  #[serde(rename = "duration_ms", serialize_with = "serialize_millis")]
+  pub duration: Duration,
+}
+
+/// Outcome of `Migration::run`. Version is the last applied migration after the run, None if nothing is applied.
+/// Interrupted run stopped on shutdown request before all steps were done
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct MigrationReport {
+  pub steps: Vec<MigrationStep>,
+  pub version: Option<String>,
+  pub interrupted: bool,
+}
+
+fn serialize_millis<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+  serializer.serialize_u64(duration.as_millis() as u64)
+}
+
+/// Format report as human-readable text, one line per migration
+pub fn report_text(report: &MigrationReport) -> String {
+  let width = report.steps.iter().map(|step| step.name.len()).max().unwrap_or(0);
+
+  let mut text = String::new();
+  if report.steps.is_empty() {
+    text.push_str("Nothing to migrate\n");
+  }
+  for step in &report.steps {
+    text.push_str(&format!(
+      "{:<width$}  {:<4}  {} ms\n",
+      step.name,
+      step.direction.to_string(),
+      step.duration.as_millis(),
+      width = width
+    ));
+  }
+  if report.interrupted {
+    text.push_str("Interrupted by shutdown request\n");
+  }
+  text.push_str(&format!("Version: {}\n", report.version.as_deref().unwrap_or("none")));
+  text
+}
+
+/// Format report as JSON object
+pub fn report_json(report: &MigrationReport) -> String {
+  serde_json::to_string_pretty(report).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod report_test {
+  use super::{report_json, report_text, MigrationReport, MigrationStep};
+  use crate::connector::db::MigrationDirection;
+  use std::time::Duration;
+
+  fn report() -> MigrationReport {
+    MigrationReport {
+      steps: vec![
+        MigrationStep {
+          name: String::from("m_2"),
+          direction: MigrationDirection::Down,
+          duration: Duration::from_millis(7),
+        },
+        MigrationStep {
+          name: String::from("m_20"),
+          direction: MigrationDirection::Up,
+          duration: Duration::from_millis(1250),
+        },
+      ],
+      version: Some(String::from("m_20")),
+      interrupted: false,
+    }
+  }
+
+  #[test]
+  fn report_as_text() {
+    assert_eq!(report_text(&report()), "m_2   down  7 ms\nm_20  up    1250 ms\nVersion: m_20\n");
+    assert_eq!(report_text(&MigrationReport::default()), "Nothing to migrate\nVersion: none\n");
+  }
+
+  #[test]
+  fn report_as_json() {
+    let json = report_json(&report());
+
+    assert!(json.contains("\"direction\": \"down\""));
+    assert!(json.contains("\"duration_ms\": 1250"));
+    assert!(json.contains("\"version\": \"m_20\""));
+  }
+}
@@ -0,0 +1,47 @@
+use sea_orm::DbErr;
+
+use super::snapshot::snapshot_diff;
+use super::MigrationDirection;
+
+/// Check schema snapshot taken after migration was run in direction matches the expected one: rollback must restore
+/// schema from before the migration, reapply after rollback must restore schema of the first apply.
+/// Fails with diff of expected and actual schema
+pub fn check_reversal(name: &str, direction: MigrationDirection, expected: &str, actual: &str) -> Result<(), DbErr> {
+  let diff = snapshot_diff(expected, actual);
+  if diff.is_empty() {
+    return Ok(());
+  }
+
+  let step = match direction {
+    MigrationDirection::Down => "rollback does not restore schema from before it",
+    MigrationDirection::Up => "reapply after rollback does not restore schema of the first apply",
+  };
+  Err(DbErr::Custom(format!("Migration {} is not reversible, {}:\n{}", name, step, diff.join("\n"))))
+}
+
+#[cfg(test)]
+mod reversibility_test {
+  use super::check_reversal;
+  use crate::connector::db::MigrationDirection;
+
+  const BEFORE: &str = "table bakery\n  column id integer NOT NULL\n";
+  const APPLIED: &str = "table bakery\n  column id integer NOT NULL\n  column name varchar NOT NULL\n";
+
+  #[test]
+  fn symmetric_migration() {
+    assert!(check_reversal("m_1", MigrationDirection::Down, BEFORE, BEFORE).is_ok());
+    assert!(check_reversal("m_1", MigrationDirection::Up, APPLIED, APPLIED).is_ok());
+  }
+
+  #[test]
+  fn rollback_leaves_column() {
+    match check_reversal("m_1", MigrationDirection::Down, BEFORE, APPLIED) {
+      Err(e) => {
+        let message = e.to_string();
+        assert!(message.contains("Migration m_1 is not reversible, rollback does not restore schema"));
+        assert!(message.contains("+  column name varchar NOT NULL"));
+      },
+      Ok(_) => panic!(),
+    }
+  }
+}
@@ -0,0 +1,272 @@
+#[cfg(feature = "entities")]
+use chrono::TimeZone;
+use chrono::{DateTime, Utc};
+#[cfg(feature = "entities")]
+use log::{info, warn};
+use sea_orm::prelude::async_trait;
+#[cfg(feature = "entities")]
+use sea_orm::{ConnectionTrait, DatabaseBackend, DatabaseConnection, Statement, TransactionTrait};
+use sea_orm::{DatabaseTransaction, DbErr};
+#[cfg(feature = "entities")]
+use sea_orm_migration::SchemaManager;
+use serde::Serialize;
+
+#[cfg(feature = "entities")]
+use crate::profile::Profile;
+#[cfg(feature = "entities")]
+use crate::seeder::Seeds;
+#[cfg(feature = "entities")]
+use crate::shutdown::Shutdown;
+
+/// Table holding seeds which already ran
+pub const SEED_TABLE: &str = "seed_history";
+
+/// Uses to populate DB with baseline data. Seeders are registered in `Seeds::seeders()` and run once, in order
+#[async_trait::async_trait]
+pub trait Seeder: Send + Sync {
+  /// Unique name of the seed, recorded once it ran
+  fn name(&self) -> &str;
+
+  /// Profiles the seed runs on, e.g. `vec!["development", "staging"]`. Empty list means every profile
+  fn profiles(&self) -> Vec<&str> {
+    Vec::new()
+  }
+
+  /// Insert data. Runs inside transaction, which is committed together with the seed record
+  async fn run(&self, connection: &DatabaseTransaction) -> Result<(), DbErr>;
+}
+
+/// Uses to describe state of single seed from `Seeds::seeders()` on the profile
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SeedState {
+  pub name: String,
+  pub applied: bool,
+  pub applied_at: Option<DateTime<Utc>>,
+}
+
+/// Uses to run seeders of the profile. Needs "entities" feature, as seeders use generated entities.
+/// Initialize by running:
+/// ```
+/// use rust_orm_template::connector::db::Seed;
+///
+/// let seed = Seed::new();
+/// ```
+#[cfg(feature = "entities")]
+#[derive(Debug, Clone)]
+pub struct Seed {
+  profile: Profile,
+  shutdown: Option<Shutdown>,
+}
+
+#[cfg(feature = "entities")]
+impl Seed {
+  /// Uses to initialize seeding. Profile is read from APP_PROFILE ENV
+  #[allow(dead_code)]
+  pub fn new() -> Seed {
+    Seed { profile: Profile::from_env(), shutdown: None }
+  }
+
+  /// Uses to override profile read from APP_PROFILE ENV
+  #[allow(dead_code)]
+  pub fn with_profile(mut self, profile: Profile) -> Seed {
+    self.profile = profile;
+    self
+  }
+
+  /// Uses to stop seeding between seeders once shutdown is requested. Seeder being run is never interrupted
+  #[allow(dead_code)]
+  pub fn with_shutdown(mut self, shutdown: Shutdown) -> Seed {
+    self.shutdown = Some(shutdown);
+    self
+  }
+
+  /// Names of seeders registered for the profile in order of `Seeds::seeders()`
+  pub fn names(&self) -> Vec<String> {
+    Seeds::seeders()
+      .iter()
+      .filter(|seeder| seeder.profiles().is_empty() || seeder.profiles().contains(&self.profile.name()))
+      .map(|seeder| seeder.name().to_string())
+      .collect()
+  }
+
+  /// Get state of every seeder registered for the profile. Does not create seed table
+  #[allow(dead_code)]
+  pub async fn status(&self, connection: &DatabaseConnection) -> Result<Vec<SeedState>, DbErr> {
+    let applied = Seed::applied(connection).await?;
+
+    Ok(
+      self
+        .names()
+        .into_iter()
+        .map(|name| {
+          let applied_at = applied.iter().find(|(applied_name, _)| *applied_name == name).map(|(_, at)| *at);
+          SeedState {
+            applied: applied_at.is_some(),
+            applied_at: applied_at.and_then(|secs| Utc.timestamp_opt(secs, 0).single()),
+            name,
+          }
+        })
+        .collect(),
+    )
+  }
+
+  /// Run every seeder of the profile which did not run yet, each one in its own transaction.
+  /// Returns names of seeders which ran
+  #[allow(dead_code)]
+  pub async fn run(&self, connection: &DatabaseConnection) -> Result<Vec<String>, DbErr> {
+    connection
+      .execute_unprepared(&format!(
+        "CREATE TABLE IF NOT EXISTS {} (name varchar PRIMARY KEY, profile varchar NOT NULL, applied_at bigint NOT NULL)",
+        SEED_TABLE
+      ))
+      .await?;
+    let applied = Seed::applied(connection).await?;
+    let names = self.names();
+
+    let mut ran = Vec::new();
+    for seeder in Seeds::seeders() {
+      let name = seeder.name().to_string();
+      if !names.contains(&name) || applied.iter().any(|(applied_name, _)| *applied_name == name) {
+        continue;
+      }
+      if self.shutdown.as_ref().is_some_and(Shutdown::is_requested) {
+        warn!("Shutdown requested, remaining seeders are skipped");
+        break;
+      }
+
+      info!("Running seed '{}'", name);
+      let transaction = connection.begin().await?;
+      match self.run_seeder(&transaction, seeder.as_ref()).await {
+        Ok(_) => transaction.commit().await?,
+        Err(e) => {
+          transaction.rollback().await?;
+          return Err(e);
+        },
+      }
+      ran.push(name);
+    }
+
+    Ok(ran)
+  }
+
+  /// Run seeder and record it inside transaction
+  async fn run_seeder(&self, transaction: &DatabaseTransaction, seeder: &dyn Seeder) -> Result<(), DbErr> {
+    seeder.run(transaction).await?;
+    transaction
+      .execute(Statement::from_sql_and_values(
+        DatabaseBackend::Postgres,
+        &format!("INSERT INTO {} (name, profile, applied_at) VALUES ($1, $2, $3)", SEED_TABLE),
+        [seeder.name().into(), self.profile.name().into(), Utc::now().timestamp().into()],
+      ))
+      .await?;
+    Ok(())
+  }
+
+  /// Names and run time of seeds which already ran
+  async fn applied(connection: &DatabaseConnection) -> Result<Vec<(String, i64)>, DbErr> {
+    if !SchemaManager::new(connection).has_table(SEED_TABLE).await? {
+      return Ok(Vec::new());
+    }
+
+    let rows = connection
+      .query_all(Statement::from_string(
+        DatabaseBackend::Postgres,
+        format!("SELECT name, applied_at FROM {} ORDER BY applied_at, name", SEED_TABLE),
+      ))
+      .await?;
+
+    let mut applied = Vec::new();
+    for row in rows {
+      applied.push((row.try_get::<String>("", "name")?, row.try_get::<i64>("", "applied_at")?));
+    }
+    Ok(applied)
+  }
+}
+
+#[cfg(feature = "entities")]
+impl Default for Seed {
+  fn default() -> Seed {
+    Seed::new()
+  }
+}
+
+/// Format seed states as human-readable table
+pub fn seed_table(states: &[SeedState]) -> String {
+  let width = states.iter().map(|state| state.name.len()).max().unwrap_or(0).max("Seed".len());
+
+  let mut table = format!("{:<width$}  {:<7}  {}\n", "Seed", "Status", "Ran at", width = width);
+  for state in states {
+    let status = if state.applied { "applied" } else { "pending" };
+    let applied_at = state.applied_at.map(|at| at.format("%Y-%m-%d %H:%M:%S UTC").to_string()).unwrap_or_default();
+    table.push_str(format!("{:<width$}  {:<7}  {}", state.name, status, applied_at, width = width).trim_end());
+    table.push('\n');
+  }
+  table
+}
+
+#[cfg(all(test, feature = "entities"))]
+mod seed_test {
+  use super::{seed_table, Seed, SeedState};
+  use crate::profile::Profile;
+  use crate::shutdown::Shutdown;
+  use chrono::{TimeZone, Utc};
+  use futures::executor::block_on;
+  use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult, Value};
+  use std::collections::BTreeMap;
+
+  #[test]
+  fn names_of_development() {
+    let seed = Seed::new().with_profile(Profile::new("development"));
+
+    assert_eq!(seed.names(), vec!["s_20220602_000001_seed_bakeries"]);
+  }
+
+  #[test]
+  fn names_of_production() {
+    let seed = Seed::new().with_profile(Profile::new("production"));
+
+    assert!(seed.names().is_empty());
+  }
+
+  #[test]
+  fn run_skips_applied() {
+    let connection = MockDatabase::new(DatabaseBackend::Postgres)
+      .append_exec_results([MockExecResult::default()])
+      .append_query_results([[BTreeMap::from([("has_table", Value::Bool(Some(true)))])]])
+      .append_query_results([[BTreeMap::from([
+        ("name", Value::from("s_20220602_000001_seed_bakeries")),
+        ("applied_at", Value::BigInt(Some(1654128000))),
+      ])]])
+      .into_connection();
+
+    let ran = block_on(Seed::new().with_profile(Profile::new("development")).run(&connection)).unwrap();
+
+    assert!(ran.is_empty());
+  }
+
+  #[test]
+  fn run_stops_on_shutdown() {
+    let connection = MockDatabase::new(DatabaseBackend::Postgres)
+      .append_exec_results([MockExecResult::default()])
+      .append_query_results([[BTreeMap::from([("has_table", Value::Bool(Some(false)))])]])
+      .into_connection();
+    let shutdown = Shutdown::new();
+    shutdown.request();
+
+    let seed = Seed::new().with_profile(Profile::new("development")).with_shutdown(shutdown);
+    let ran = block_on(seed.run(&connection)).unwrap();
+
+    assert!(ran.is_empty());
+    assert!(!format!("{:?}", connection.into_transaction_log()).contains("BEGIN"));
+  }
+
+  #[test]
+  fn status_formats() {
+    let states = vec![
+      SeedState { name: String::from("s_1"), applied: true, applied_at: Utc.timestamp_opt(1654128000, 0).single() },
+      SeedState { name: String::from("s_2"), applied: false, applied_at: None },
+    ];
+
+    assert_eq!(seed_table(&states), "Seed  Status   Ran at\ns_1   applied  2022-06-02 00:00:00 UTC\ns_2   pending\n");
+  }
+}
@@ -0,0 +1,193 @@
+use sea_orm::{ConnectionTrait, DatabaseBackend, DatabaseConnection, DbErr, Statement};
+
+use super::{BACKFILL_TABLE, CHANGE_TABLE, CHECKSUM_TABLE, HISTORY_TABLE, SEED_TABLE};
+
+/// File schema snapshot is written to
+pub const SNAPSHOT_FILE: &str = "schema.snapshot";
+
+/// Tables of migration and seed bookkeeping, left out of schema snapshot, drift check and generated entities
+pub const BOOKKEEPING_TABLES: [&str; 6] =
+  ["seaql_migrations", CHECKSUM_TABLE, SEED_TABLE, HISTORY_TABLE, CHANGE_TABLE, BACKFILL_TABLE];
+
+/// Columns of tables in current schema in order of table name and column position
+const COLUMNS_QUERY: &str = "SELECT c.relname AS table_name, a.attname AS name, \
+  format_type(a.atttypid, a.atttypmod) AS definition, a.attnotnull AS not_null, \
+  pg_get_expr(d.adbin, d.adrelid) AS default_value \
+  FROM pg_catalog.pg_attribute a \
+  JOIN pg_catalog.pg_class c ON c.oid = a.attrelid \
+  JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace \
+  LEFT JOIN pg_catalog.pg_attrdef d ON d.adrelid = a.attrelid AND d.adnum = a.attnum \
+  WHERE n.nspname = current_schema() AND c.relkind IN ('r', 'p') AND a.attnum > 0 AND NOT a.attisdropped \
+  ORDER BY c.relname, a.attnum";
+
+/// Primary keys, unique, check and foreign key constraints of tables in current schema
+const CONSTRAINTS_QUERY: &str = "SELECT c.relname AS table_name, con.conname AS name, \
+  pg_get_constraintdef(con.oid) AS definition \
+  FROM pg_catalog.pg_constraint con \
+  JOIN pg_catalog.pg_class c ON c.oid = con.conrelid \
+  JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace \
+  WHERE n.nspname = current_schema() \
+  ORDER BY c.relname, con.conname";
+
+/// Indexes of tables in current schema. Schema name is stripped, so snapshot does not depend on POSTGRES_SCHEMA
+const INDEXES_QUERY: &str = "SELECT tablename AS table_name, indexname AS name, \
+  replace(indexdef, ' ON ' || quote_ident(schemaname) || '.', ' ON ') AS definition \
+  FROM pg_catalog.pg_indexes \
+  WHERE schemaname = current_schema() \
+  ORDER BY tablename, indexname";
+
+/// Single line of schema snapshot: column, constraint or index of the table
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SchemaItem {
+  pub table_name: String,
+  pub kind: &'static str,
+  pub name: String,
+  pub definition: String,
+}
+
+/// Introspect tables of current schema and format them as snapshot, see `snapshot_text`
+pub async fn schema_snapshot(connection: &DatabaseConnection) -> Result<String, DbErr> {
+  let mut items = Vec::new();
+  for row in query(connection, COLUMNS_QUERY).await? {
+    let mut definition: String = row.try_get("", "definition")?;
+    if row.try_get::<bool>("", "not_null")? {
+      definition.push_str(" NOT NULL");
+    }
+    if let Some(default_value) = row.try_get::<Option<String>>("", "default_value")? {
+      definition.push_str(&format!(" DEFAULT {}", default_value));
+    }
+    items.push(SchemaItem {
+      table_name: row.try_get("", "table_name")?,
+      kind: "column",
+      name: row.try_get("", "name")?,
+      definition,
+    });
+  }
+  for (kind, sql) in [("constraint", CONSTRAINTS_QUERY), ("index", INDEXES_QUERY)] {
+    for row in query(connection, sql).await? {
+      items.push(SchemaItem {
+        table_name: row.try_get("", "table_name")?,
+        kind,
+        name: row.try_get("", "name")?,
+        definition: row.try_get("", "definition")?,
+      });
+    }
+  }
+
+  Ok(snapshot_text(&items))
+}
+
+async fn query(connection: &DatabaseConnection, sql: &str) -> Result<Vec<sea_orm::QueryResult>, DbErr> {
+  connection.query_all(Statement::from_string(DatabaseBackend::Postgres, sql.to_string())).await
+}
+
+/// Format schema items as text: one block per table in alphabetical order with columns in position order,
+/// then constraints and indexes in alphabetical order. Bookkeeping tables are left out
+pub fn snapshot_text(items: &[SchemaItem]) -> String {
+  let mut items: Vec<&SchemaItem> =
+    items.iter().filter(|item| !BOOKKEEPING_TABLES.contains(&item.table_name.as_str())).collect();
+  // Stable sort keeps columns in position order
+  items.sort_by(|a, b| (&a.table_name, kind_order(a.kind)).cmp(&(&b.table_name, kind_order(b.kind))));
+  items.dedup();
+
+  let mut text = String::new();
+  let mut table: Option<&str> = None;
+  for item in items {
+    if table != Some(item.table_name.as_str()) {
+      if table.is_some() {
+        text.push('\n');
+      }
+      text.push_str(&format!("table {}\n", item.table_name));
+      table = Some(item.table_name.as_str());
+    }
+    text.push_str(&format!("  {} {} {}\n", item.kind, item.name, item.definition));
+  }
+  text
+}
+
+fn kind_order(kind: &str) -> u8 {
+  match kind {
+    "column" => 0,
+    "constraint" => 1,
+    _ => 2,
+  }
+}
+
+/// Lines present only in expected (prefixed by "-") or only in actual snapshot (prefixed by "+"),
+/// grouped under the name of table they belong to
+pub fn snapshot_diff(expected: &str, actual: &str) -> Vec<String> {
+  let expected = snapshot_lines(expected);
+  let actual = snapshot_lines(actual);
+
+  let mut diff = Vec::new();
+  let mut table: Option<&str> = None;
+  let removed = expected.iter().filter(|line| !actual.contains(line)).map(|line| ('-', line));
+  let added = actual.iter().filter(|line| !expected.contains(line)).map(|line| ('+', line));
+  let mut changes: Vec<(char, &(String, String))> = removed.chain(added).collect();
+  changes.sort_by(|a, b| a.1 .0.cmp(&b.1 .0));
+  for (sign, (table_name, line)) in changes {
+    if table != Some(table_name.as_str()) {
+      diff.push(format!(" table {}", table_name));
+      table = Some(table_name.as_str());
+    }
+    diff.push(format!("{}{}", sign, line));
+  }
+  diff
+}
+
+/// Non-empty lines of snapshot with name of table they belong to
+fn snapshot_lines(snapshot: &str) -> Vec<(String, String)> {
+  let mut table = String::new();
+  let mut lines = Vec::new();
+  for line in snapshot.lines().filter(|line| !line.trim().is_empty()) {
+    match line.strip_prefix("table ") {
+      Some(name) => table = name.to_string(),
+      None => lines.push((table.clone(), line.to_string())),
+    }
+  }
+  lines
+}
+
+#[cfg(test)]
+mod snapshot_test {
+  use super::{snapshot_diff, snapshot_text, SchemaItem};
+
+  fn item(table_name: &str, kind: &'static str, name: &str, definition: &str) -> SchemaItem {
+    SchemaItem { table_name: table_name.to_string(), kind, name: name.to_string(), definition: definition.to_string() }
+  }
+
+  #[test]
+  fn text_is_ordered() {
+    let items = vec![
+      item("chef", "index", "chef_pkey", "CREATE UNIQUE INDEX chef_pkey ON chef USING btree (id)"),
+      item("chef", "column", "id", "integer NOT NULL"),
+      item("bakery", "constraint", "bakery_pkey", "PRIMARY KEY (id)"),
+      item("chef", "column", "name", "character varying NOT NULL"),
+      item("seaql_migrations", "column", "version", "character varying NOT NULL"),
+      item("bakery", "column", "id", "integer NOT NULL"),
+    ];
+
+    assert_eq!(
+      snapshot_text(&items),
+      "table bakery\n  column id integer NOT NULL\n  constraint bakery_pkey PRIMARY KEY (id)\n\ntable chef\n  column id \
+       integer NOT NULL\n  column name character varying NOT NULL\n  index chef_pkey CREATE UNIQUE INDEX chef_pkey ON \
+       chef USING btree (id)\n"
+    );
+  }
+
+  #[test]
+  fn diff_of_snapshots() {
+    let expected = "table chef\n  column id integer NOT NULL\n  column name text\n";
+    let actual = "table chef\n  column id integer NOT NULL\n  column name text NOT NULL\n";
+
+    assert_eq!(
+      snapshot_diff(expected, actual),
+      vec![" table chef", "-  column name text", "+  column name text NOT NULL"]
+    );
+    assert_eq!(
+      snapshot_diff("", actual),
+      vec![" table chef", "+  column id integer NOT NULL", "+  column name text NOT NULL"]
+    );
+    assert!(snapshot_diff(expected, expected).is_empty());
+  }
+}
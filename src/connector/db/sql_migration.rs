@@ -0,0 +1,198 @@
+use sea_orm::prelude::async_trait;
+use sea_orm::{ConnectionTrait, DbErr};
+use sea_orm_migration::{MigrationName, MigrationTrait, SchemaManager};
+
+use super::MISSING_DOWN;
+
+/// Migration written in plain SQL files "YYYYMMDD_HHMMSS_name.up.sql" and optional "YYYYMMDD_HHMMSS_name.down.sql"
+/// of `src/migrator/sql`. Files are embedded at compile time by build script. Name of migration is
+/// "YYYYMMDD_HHMMSS_name", so its version is comparable with version of Rust migrations
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SqlMigration {
+  pub name: &'static str,
+  pub up: &'static str,
+  pub down: Option<&'static str>,
+}
+
+impl SqlMigration {
+  /// Build migrations from list of (name, up, down) embedded by build script
+  pub fn from_files(files: &[(&'static str, &'static str, Option<&'static str>)]) -> Vec<SqlMigration> {
+    files.iter().map(|(name, up, down)| SqlMigration { name, up, down: *down }).collect()
+  }
+}
+
+impl MigrationName for SqlMigration {
+  fn name(&self) -> &str {
+    self.name
+  }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for SqlMigration {
+  async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+    manager.get_connection().execute_unprepared(self.up).await?;
+    Ok(())
+  }
+
+  async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+    match self.down {
+      Some(down) => {
+        manager.get_connection().execute_unprepared(down).await?;
+        Ok(())
+      },
+      // The same error as default `MigrationTrait::down`, so lint reports missing down
+      None => Err(DbErr::Migration(MISSING_DOWN.to_string())),
+    }
+  }
+}
+
+/// Version of migration given by leading digits of its name, ignoring "m" prefix and underscores,
+/// e.g. 20220602000001 of "m_20220602_000001_create_bakery_table" and 20220603120000 of "20220603_120000_add_oven"
+pub fn migration_version(name: &str) -> u64 {
+  let digits: String = name
+    .trim_start_matches('m')
+    .chars()
+    .take_while(|c| c.is_ascii_digit() || *c == '_')
+    .filter(char::is_ascii_digit)
+    .collect();
+  digits.parse().unwrap_or(0)
+}
+
+/// Merge Rust and SQL migrations in order of version. Migrations of the same version keep their order,
+/// Rust ones first
+pub fn interleave(
+  migrations: Vec<Box<dyn MigrationTrait>>,
+  sql: &[(&'static str, &'static str, Option<&'static str>)],
+) -> Vec<Box<dyn MigrationTrait>> {
+  let mut migrations = migrations;
+  for migration in SqlMigration::from_files(sql) {
+    migrations.push(Box::new(migration));
+  }
+  migrations.sort_by_key(|migration| migration_version(migration.name()));
+  migrations
+}
+
+/// Split SQL script into statements at semicolons outside of quotes, dollar quotes and comments.
+/// Comments are left out
+pub fn split_statements(sql: &str) -> Vec<String> {
+  let mut statements = Vec::new();
+  let mut statement = String::new();
+  let mut chars = sql.chars().peekable();
+  while let Some(c) = chars.next() {
+    match c {
+      '-' if chars.peek() == Some(&'-') => {
+        for c in chars.by_ref() {
+          if c == '\n' {
+            statement.push('\n');
+            break;
+          }
+        }
+      },
+      '\'' | '"' => {
+        statement.push(c);
+        for next in chars.by_ref() {
+          statement.push(next);
+          if next == c {
+            break;
+          }
+        }
+      },
+      '$' => {
+        let mut tag = String::from("$");
+        while let Some(next) = chars.peek().copied().filter(|next| next.is_alphanumeric() || *next == '_') {
+          tag.push(next);
+          chars.next();
+        }
+        if chars.peek() != Some(&'$') {
+          statement.push_str(&tag);
+          continue;
+        }
+        chars.next();
+        tag.push('$');
+        statement.push_str(&tag);
+        let mut body = String::new();
+        for next in chars.by_ref() {
+          body.push(next);
+          if body.ends_with(&tag) {
+            break;
+          }
+        }
+        statement.push_str(&body);
+      },
+      ';' => {
+        statements.push(statement.trim().to_string());
+        statement.clear();
+      },
+      _ => statement.push(c),
+    }
+  }
+  statements.push(statement.trim().to_string());
+  statements.retain(|statement| !statement.is_empty());
+  statements
+}
+
+#[cfg(test)]
+mod sql_migration_test {
+  use super::{interleave, migration_version, split_statements, SqlMigration};
+  use crate::connector::db::{record_migration, MigrationDirection};
+  use crate::migrator::Migrator;
+  use futures::executor::block_on;
+  use sea_orm::DbErr;
+  use sea_orm_migration::MigratorTrait;
+
+  const FILES: [(&str, &str, Option<&str>); 2] = [
+    ("20220603_120000_add_chef_rating", "ALTER TABLE chef ADD COLUMN rating smallint;", None),
+    ("20220602_000001_create_oven", "CREATE TABLE oven (id serial PRIMARY KEY);", Some("DROP TABLE oven;")),
+  ];
+
+  #[test]
+  fn versions() {
+    assert_eq!(migration_version("m_20220602_000001_create_bakery_table"), 20220602000001);
+    assert_eq!(migration_version("m20220602_000001_create_bakery_table"), 20220602000001);
+    assert_eq!(migration_version("20220603_120000_add_oven"), 20220603120000);
+  }
+
+  #[test]
+  fn interleave_by_version() {
+    let names: Vec<String> =
+      interleave(Migrator::migrations(), &FILES).iter().map(|migration| migration.name().to_string()).collect();
+
+    assert_eq!(
+      names,
+      vec![
+        "m_20220602_000001_create_bakery_table",
+        "20220602_000001_create_oven",
+        "m_20220602_000002_create_chef_table",
+        "20220603_120000_add_chef_rating",
+      ]
+    );
+  }
+
+  #[test]
+  fn run_sql_files() {
+    let migrations = SqlMigration::from_files(&FILES);
+
+    assert_eq!(
+      block_on(record_migration(&migrations[1], MigrationDirection::Down)).unwrap(),
+      vec![String::from("DROP TABLE oven;")]
+    );
+    match block_on(record_migration(&migrations[0], MigrationDirection::Down)) {
+      Err(DbErr::Migration(message)) => assert_eq!(message, "We Don't Do That Here"),
+      _ => panic!(),
+    }
+  }
+
+  #[test]
+  fn split_script() {
+    let sql = "-- add rating; with default\nALTER TABLE chef ADD COLUMN rating smallint DEFAULT 0;\n\
+               CREATE FUNCTION f() RETURNS text AS $body$ SELECT 'a;b' $body$ LANGUAGE sql;\n";
+
+    assert_eq!(
+      split_statements(sql),
+      vec![
+        "ALTER TABLE chef ADD COLUMN rating smallint DEFAULT 0",
+        "CREATE FUNCTION f() RETURNS text AS $body$ SELECT 'a;b' $body$ LANGUAGE sql",
+      ]
+    );
+  }
+}
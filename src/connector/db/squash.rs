@@ -0,0 +1,295 @@
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use log::info;
+use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseTransaction, DbErr, EntityTrait, QueryFilter, Set};
+use sea_orm_migration::{seaql_migrations, MigratorTrait};
+
+use super::dry_run::record_migration;
+use super::transaction::{transaction_mode, TransactionMode};
+use super::MigrationDirection;
+use crate::migrator::{Migrator, SQUASHED};
+
+const BASELINE_TEMPLATE: &str = r#"use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+  fn name(&self) -> &str {
+    "{name}"
+  }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+  // Define how to apply this migration: Create schema of squashed migrations.
+  async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+    for statement in UP {
+      manager.get_connection().execute_unprepared(statement).await?;
+    }
+    Ok(())
+  }
+
+  // Define how to rollback this migration: Drop schema of squashed migrations.
+  async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+    for statement in DOWN {
+      manager.get_connection().execute_unprepared(statement).await?;
+    }
+    Ok(())
+  }
+}
+
+const UP: [&str; {up_len}] = [
+{up}];
+
+const DOWN: [&str; {down_len}] = [
+{down}];
+"#;
+
+const MIGRATOR_TEMPLATE: &str = r#"use sea_orm_migration::prelude::*;
+
+use crate::connector::db::{interleave, TransactionMode};
+
+mod {module};
+
+/// Names of migrations squashed into the first migration. DB which ran them is marked as having run it
+pub const SQUASHED: &[&str] = &[
+{squashed}];
+
+/// Transaction modes of migrations not run in batch transaction, see `TransactionMode`.
+/// Modes of SQL migrations are declared by "-- transaction: <mode>" header of their up file instead
+pub const TRANSACTION_MODES: &[(&str, TransactionMode)] = &[{transaction_modes}];
+
+/// Plain SQL migrations of `sql` directory as (name, up, down), embedded by build script
+pub const SQL_MIGRATIONS: &[(&str, &str, Option<&str>)] = include!(concat!(env!("OUT_DIR"), "/sql_migrations.rs"));
+
+pub struct Migrator;
+
+#[async_trait::async_trait]
+impl MigratorTrait for Migrator {
+  fn migrations() -> Vec<Box<dyn MigrationTrait>> {
+    interleave(rust_migrations(), SQL_MIGRATIONS)
+  }
+}
+
+/// Migrations written in Rust. New ones are added at the end by `new_migration`
+fn rust_migrations() -> Vec<Box<dyn MigrationTrait>> {
+  vec![
+    Box::new({module}::Migration),
+  ]
+}
+"#;
+
+/// Baseline migration written by `squash`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Baseline {
+  pub module: String,
+  pub name: String,
+  pub squashed: Vec<String>,
+  pub up: Vec<String>,
+  pub down: Vec<String>,
+  pub transaction: TransactionMode,
+}
+
+impl Baseline {
+  /// Record SQL of every migration of `Migrator::migrations()`: up in order, down in reverse order.
+  /// Names of migrations squashed before are kept, so DB which did not run previous baseline is still recognized.
+  /// Baseline runs without transaction if any squashed migration does, in its own transaction if any squashed one does
+  pub async fn record(created_at: DateTime<Utc>) -> Result<Baseline, DbErr> {
+    let migrations = Migrator::migrations();
+    if migrations.is_empty() {
+      return Err(DbErr::Custom(String::from("There are no migrations to squash")));
+    }
+
+    let mut up = Vec::new();
+    let mut down = Vec::new();
+    for migration in &migrations {
+      let error = |e: DbErr| DbErr::Custom(format!("Migration {} cannot be squashed: {}", migration.name(), e));
+      up.extend(record_migration(migration.as_ref(), MigrationDirection::Up).await.map_err(error)?);
+    }
+    for migration in migrations.iter().rev() {
+      let error = |e: DbErr| DbErr::Custom(format!("Migration {} cannot be squashed: {}", migration.name(), e));
+      down.extend(record_migration(migration.as_ref(), MigrationDirection::Down).await.map_err(error)?);
+    }
+
+    let modes: Vec<TransactionMode> = migrations.iter().map(|migration| transaction_mode(migration.name())).collect();
+    let transaction =
+      [TransactionMode::None, TransactionMode::Own].into_iter().find(|mode| modes.contains(mode)).unwrap_or_default();
+
+    let timestamp = created_at.format("%Y%m%d_%H%M%S");
+    let mut squashed: Vec<String> = SQUASHED.iter().map(|name| name.to_string()).collect();
+    squashed.extend(migrations.iter().map(|migration| migration.name().to_string()));
+    Ok(Baseline {
+      module: format!("m{}_baseline", timestamp),
+      name: format!("m_{}_baseline", timestamp),
+      squashed,
+      up,
+      down,
+      transaction,
+    })
+  }
+
+  /// Rust source of baseline migration, running recorded SQL
+  pub fn source(&self) -> String {
+    let statements =
+      |statements: &[String]| -> String { statements.iter().map(|s| format!("  {},\n", raw_string(s))).collect() };
+
+    BASELINE_TEMPLATE
+      .replace("{name}", &self.name)
+      .replace("{up_len}", &self.up.len().to_string())
+      .replace("{up}", &statements(&self.up))
+      .replace("{down_len}", &self.down.len().to_string())
+      .replace("{down}", &statements(&self.down))
+  }
+
+  /// Rust source of migrator registering baseline only. Layout is kept parsable by `new_migration`
+  pub fn migrator_source(&self) -> String {
+    let squashed: String = self.squashed.iter().map(|name| format!("  \"{}\",\n", name)).collect();
+    let transaction_modes = match self.transaction {
+      TransactionMode::Batch => String::new(),
+      mode => format!("\n  (\"{}\", TransactionMode::{:?}),\n", self.name, mode),
+    };
+
+    MIGRATOR_TEMPLATE
+      .replace("{module}", &self.module)
+      .replace("{squashed}", &squashed)
+      .replace("{transaction_modes}", &transaction_modes)
+  }
+
+  /// Replace every migration module and SQL migration of migrator directory with baseline and rewrite its mod.rs
+  pub fn write(&self, dir: &str) -> Result<(), Error> {
+    let dir = Path::new(dir);
+    if !dir.join("mod.rs").exists() {
+      return Err(Error::new(ErrorKind::NotFound, format!("{}/mod.rs is not found", dir.display())));
+    }
+    for entry in fs::read_dir(dir)? {
+      let path = entry?.path();
+      let is_module = path.extension().is_some_and(|extension| extension == "rs");
+      if is_module && path.file_name().is_some_and(|name| name != "mod.rs") {
+        fs::remove_file(&path)?;
+      }
+    }
+    if dir.join("sql").is_dir() {
+      for entry in fs::read_dir(dir.join("sql"))? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|extension| extension == "sql") {
+          fs::remove_file(&path)?;
+        }
+      }
+    }
+    fs::write(dir.join(format!("{}.rs", self.module)), self.source())?;
+    fs::write(dir.join("mod.rs"), self.migrator_source())?;
+    info!("Squashed {} migrations into {}", self.squashed.len(), self.name);
+    Ok(())
+  }
+}
+
+/// Whether baseline is to be marked as applied, given versions applied to DB. It is, once DB ran the last of squashed
+/// migrations and did not run baseline. DB which ran part of them must finish with release preceding the squash
+pub fn is_baseline_due(applied: &[String], baseline: &str, squashed: &[&str]) -> Result<bool, DbErr> {
+  let last = match squashed.last() {
+    Some(last) => *last,
+    None => return Ok(false),
+  };
+  if applied.iter().any(|name| name == baseline) || !applied.iter().any(|name| squashed.contains(&name.as_str())) {
+    return Ok(false);
+  }
+  if applied.iter().any(|name| name == last) {
+    return Ok(true);
+  }
+  Err(DbErr::Custom(format!(
+    "DB ran part of migrations squashed into {}, migrate it up to {} with release preceding the squash",
+    baseline, last
+  )))
+}
+
+/// Replace records of squashed migrations by record of baseline if DB ran squashed migrations.
+/// Returns versions applied to DB afterwards
+pub async fn mark_baseline(transaction: &DatabaseTransaction, applied: Vec<String>) -> Result<Vec<String>, DbErr> {
+  let migrations = Migrator::migrations();
+  let baseline = match migrations.first() {
+    Some(baseline) if !SQUASHED.is_empty() => baseline.name().to_string(),
+    _ => return Ok(applied),
+  };
+  if !is_baseline_due(&applied, &baseline, SQUASHED)? {
+    return Ok(applied);
+  }
+
+  info!("Marking baseline migration '{}' as applied", baseline);
+  seaql_migrations::Entity::delete_many()
+    .filter(seaql_migrations::Column::Version.is_in(SQUASHED.iter().copied()))
+    .exec(transaction)
+    .await?;
+  seaql_migrations::ActiveModel { version: Set(baseline.clone()), applied_at: Set(Utc::now().timestamp()) }
+    .insert(transaction)
+    .await?;
+
+  let mut applied: Vec<String> = applied.into_iter().filter(|name| !SQUASHED.contains(&name.as_str())).collect();
+  applied.insert(0, baseline);
+  Ok(applied)
+}
+
+/// Rust raw string literal holding the statement
+fn raw_string(statement: &str) -> String {
+  let mut hashes = String::from("#");
+  while statement.contains(&format!("\"{}", hashes)) {
+    hashes.push('#');
+  }
+  format!("r{hashes}\"{statement}\"{hashes}", hashes = hashes, statement = statement)
+}
+
+#[cfg(test)]
+mod squash_test {
+  use super::{is_baseline_due, raw_string, Baseline, TransactionMode};
+  use chrono::{TimeZone, Utc};
+  use futures::executor::block_on;
+
+  const SQUASHED: [&str; 2] = ["m_1", "m_2"];
+
+  fn applied(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+  }
+
+  #[test]
+  fn baseline_is_due() {
+    assert!(is_baseline_due(&applied(&["m_1", "m_2", "m_3"]), "m_0_baseline", &SQUASHED).unwrap());
+    assert!(!is_baseline_due(&applied(&[]), "m_0_baseline", &SQUASHED).unwrap());
+    assert!(!is_baseline_due(&applied(&["m_0_baseline", "m_3"]), "m_0_baseline", &SQUASHED).unwrap());
+    assert!(!is_baseline_due(&applied(&["m_1"]), "m_1", &[]).unwrap());
+    match is_baseline_due(&applied(&["m_1"]), "m_0_baseline", &SQUASHED) {
+      Err(e) => assert!(e.to_string().contains("migrate it up to m_2")),
+      Ok(_) => panic!(),
+    }
+  }
+
+  #[test]
+  fn record_baseline() {
+    let baseline = block_on(Baseline::record(Utc.with_ymd_and_hms(2022, 6, 3, 10, 0, 0).unwrap())).unwrap();
+
+    assert_eq!(baseline.module, "m20220603_100000_baseline");
+    assert_eq!(baseline.name, "m_20220603_100000_baseline");
+    assert_eq!(baseline.squashed, vec!["m_20220602_000001_create_bakery_table", "m_20220602_000002_create_chef_table"]);
+    assert!(baseline.up[0].starts_with("CREATE TABLE \"bakery\""));
+    assert_eq!(baseline.down, vec!["DROP TABLE \"chef\"", "DROP TABLE \"bakery\""]);
+    assert_eq!(baseline.transaction, TransactionMode::Batch);
+
+    let source = baseline.source();
+    assert!(source.contains("\"m_20220603_100000_baseline\""));
+    assert!(
+      source.contains("const DOWN: [&str; 2] = [\n  r#\"DROP TABLE \"chef\"\"#,\n  r#\"DROP TABLE \"bakery\"\"#,\n];")
+    );
+    let migrator = baseline.migrator_source();
+    assert!(migrator.contains("mod m20220603_100000_baseline;\n"));
+    assert!(migrator.contains("TRANSACTION_MODES: &[(&str, TransactionMode)] = &[];\n"));
+    let migrator = Baseline { transaction: TransactionMode::None, ..baseline }.migrator_source();
+    assert!(migrator.contains("= &[\n  (\"m_20220603_100000_baseline\", TransactionMode::None),\n];\n"));
+    assert!(migrator.contains("  vec![\n    Box::new(m20220603_100000_baseline::Migration),\n  ]\n"));
+  }
+
+  #[test]
+  fn raw_string_of_quoted_hash() {
+    assert_eq!(raw_string("SELECT '\"#'"), "r##\"SELECT '\"#'\"##");
+  }
+}
@@ -0,0 +1,103 @@
+use std::fmt;
+
+use crate::migrator::{SQL_MIGRATIONS, TRANSACTION_MODES};
+
+/// Header of SQL migration declaring its transaction mode, e.g. "-- transaction: none" as the first line of up file
+const HEADER: &str = "-- transaction:";
+
+/// Transaction migration is applied or rolled back in, in both directions:
+/// * TransactionMode::Batch - Transaction shared with neighbouring Batch migrations of the run. Default one
+/// * TransactionMode::Own - Own transaction, committed once the migration is done
+/// * TransactionMode::None - No transaction, for statements which cannot run inside transaction block,
+///   e.g. `CREATE INDEX CONCURRENTLY` or `ALTER TYPE ... ADD VALUE`. Required by `Backfill` of `ColumnChange`
+///
+/// Migrations run before Own or None one are committed before it runs, so failure later in the run keeps them.
+/// Failed None migration may be left half applied, so it should consist of single statement.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TransactionMode {
+  #[default]
+  Batch,
+  Own,
+  None,
+}
+
+impl TransactionMode {
+  /// Build TransactionMode from string. Allowed values: "batch", "own" and "none" in any case
+  pub fn from_string(value: &str) -> Option<TransactionMode> {
+    match value.trim().to_lowercase().as_str() {
+      "batch" => Some(TransactionMode::Batch),
+      "own" => Some(TransactionMode::Own),
+      "none" => Some(TransactionMode::None),
+      _ => None,
+    }
+  }
+
+  /// Mode declared by "-- transaction: <mode>" header of SQL script. Only leading comment lines are searched
+  pub fn from_header(sql: &str) -> Option<TransactionMode> {
+    sql
+      .lines()
+      .map(str::trim)
+      .take_while(|line| line.is_empty() || line.starts_with("--"))
+      .find_map(|line| line.strip_prefix(HEADER))
+      .and_then(TransactionMode::from_string)
+  }
+}
+
+impl fmt::Display for TransactionMode {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      TransactionMode::Batch => write!(f, "batch"),
+      TransactionMode::Own => write!(f, "own"),
+      TransactionMode::None => write!(f, "none"),
+    }
+  }
+}
+
+/// Transaction mode of migration: declared in `TRANSACTION_MODES` of migrator for Rust migrations,
+/// by header of up file for SQL ones, Batch otherwise
+pub fn transaction_mode(name: &str) -> TransactionMode {
+  declared_mode(name, TRANSACTION_MODES, SQL_MIGRATIONS).unwrap_or_default()
+}
+
+fn declared_mode(
+  name: &str,
+  modes: &[(&str, TransactionMode)],
+  sql: &[(&str, &str, Option<&str>)],
+) -> Option<TransactionMode> {
+  modes.iter().find(|(migration, _)| *migration == name).map(|(_, mode)| *mode).or_else(|| {
+    sql.iter().find(|(migration, _, _)| *migration == name).and_then(|(_, up, _)| TransactionMode::from_header(up))
+  })
+}
+
+#[cfg(test)]
+mod transaction_test {
+  use super::{declared_mode, transaction_mode, TransactionMode};
+
+  const SQL: [(&str, &str, Option<&str>); 2] = [
+    (
+      "20220603_120000_index_chef_name",
+      "-- Index without blocking writes\n-- transaction: none\nCREATE INDEX CONCURRENTLY idx_chef_name ON chef (name);",
+      Some("-- transaction: none\nDROP INDEX CONCURRENTLY idx_chef_name;"),
+    ),
+    ("20220603_130000_add_rating", "ALTER TABLE chef ADD COLUMN rating smallint;\n-- transaction: none", None),
+  ];
+
+  #[test]
+  fn mode_from_header() {
+    assert_eq!(TransactionMode::from_header(SQL[0].1), Some(TransactionMode::None));
+    assert_eq!(TransactionMode::from_header("-- transaction: OWN\nSELECT 1;"), Some(TransactionMode::Own));
+    assert_eq!(TransactionMode::from_header(SQL[1].1), None);
+    assert_eq!(TransactionMode::from_header("-- transaction: sometimes\nSELECT 1;"), None);
+  }
+
+  #[test]
+  fn declared_modes() {
+    let modes = [("m_1", TransactionMode::Own), ("20220603_120000_index_chef_name", TransactionMode::Batch)];
+
+    assert_eq!(declared_mode("m_1", &modes, &SQL), Some(TransactionMode::Own));
+    assert_eq!(declared_mode("20220603_120000_index_chef_name", &modes, &SQL), Some(TransactionMode::Batch));
+    assert_eq!(declared_mode("20220603_120000_index_chef_name", &[], &SQL), Some(TransactionMode::None));
+    assert_eq!(declared_mode("20220603_130000_add_rating", &modes, &SQL), None);
+    assert_eq!(transaction_mode("m_20220602_000001_create_bakery_table"), TransactionMode::Batch);
+  }
+}
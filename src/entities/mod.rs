@@ -4,3 +4,10 @@ pub mod prelude;
 
 pub mod bakery;
 pub mod chef;
+
+use crate::connector::db::TableSchema;
+
+/// Schemas of every entity, compared with DB by drift check. Appended by `entities generate`
+pub fn all_schemas() -> Vec<TableSchema> {
+  vec![TableSchema::of(bakery::Entity), TableSchema::of(chef::Entity)]
+}
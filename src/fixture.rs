@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Error, ErrorKind};
+use std::path::Path;
+
+use log::info;
+use sea_orm::{
+  ActiveModelTrait, ActiveValue::NotSet, ConnectionTrait, DatabaseConnection, DatabaseTransaction, DbErr, EntityName,
+  Set, TransactionTrait,
+};
+use serde::Deserialize;
+
+use crate::connector::db::quote_ident;
+use crate::entities::{bakery, chef};
+
+/// Bakery row. Reference names the bakery for chefs of the same fixture file
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct BakeryFixture {
+  #[serde(rename = "ref")]
+  pub reference: Option<String>,
+  pub id: Option<i32>,
+  pub name: String,
+  pub profit_margin: f64,
+}
+
+/// Chef row. Bakery is given by reference of bakery fixture
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ChefFixture {
+  pub id: Option<i32>,
+  pub name: String,
+  pub contact_details: Option<serde_json::Value>,
+  pub bakery: String,
+}
+
+/// Uses to load rows described in JSON or YAML file, e.g.
+/// ```yaml
+/// bakery:
+///   - ref: happy
+///     name: Happy Bakery
+///     profit_margin: 0.0
+/// chef:
+///   - name: Jolie
+///     contact_details:
+///       email: jolie@example.com
+///     bakery: happy
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct Fixtures {
+  #[serde(default)]
+  pub bakery: Vec<BakeryFixture>,
+  #[serde(default)]
+  pub chef: Vec<ChefFixture>,
+}
+
+impl Fixtures {
+  /// Read fixtures from file. Format is chosen by extension: ".json", ".yaml" or ".yml"
+  pub fn from_file(path: &str) -> Result<Fixtures, Error> {
+    let content = fs::read_to_string(path)?;
+    match Path::new(path).extension().and_then(|extension| extension.to_str()) {
+      Some("json") => Fixtures::from_json(&content),
+      Some("yaml") | Some("yml") => Fixtures::from_yaml(&content),
+      _ => Err(Error::new(ErrorKind::InvalidInput, format!("Unsupported fixture file {}", path))),
+    }
+  }
+
+  /// Parse fixtures from JSON
+  pub fn from_json(content: &str) -> Result<Fixtures, Error> {
+    serde_json::from_str(content).map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string()))
+  }
+
+  /// Parse fixtures from YAML
+  pub fn from_yaml(content: &str) -> Result<Fixtures, Error> {
+    serde_yaml::from_str(content).map_err(|e| Error::new(ErrorKind::InvalidData, e.to_string()))
+  }
+
+  /// Insert every row in single transaction, then move sequences past the highest ids, so rows with explicit
+  /// ids do not break later inserts. Returns ids of referenced bakeries
+  pub async fn load(&self, connection: &DatabaseConnection) -> Result<HashMap<String, i32>, DbErr> {
+    let transaction = connection.begin().await?;
+    match self.insert(&transaction).await {
+      Ok(bakeries) => {
+        transaction.commit().await?;
+        Ok(bakeries)
+      },
+      Err(e) => {
+        transaction.rollback().await?;
+        Err(e)
+      },
+    }
+  }
+
+  async fn insert(&self, transaction: &DatabaseTransaction) -> Result<HashMap<String, i32>, DbErr> {
+    let mut bakeries = HashMap::new();
+    for fixture in &self.bakery {
+      let bakery = bakery::ActiveModel {
+        id: fixture.id.map_or(NotSet, Set),
+        name: Set(fixture.name.clone()),
+        profit_margin: Set(fixture.profit_margin),
+      }
+      .insert(transaction)
+      .await?;
+      if let Some(reference) = &fixture.reference {
+        bakeries.insert(reference.clone(), bakery.id);
+      }
+    }
+
+    for fixture in &self.chef {
+      let bakery_id = *bakeries.get(&fixture.bakery).ok_or_else(|| {
+        DbErr::Custom(format!("Unknown bakery reference {} of chef {}", fixture.bakery, fixture.name))
+      })?;
+      chef::ActiveModel {
+        id: fixture.id.map_or(NotSet, Set),
+        name: Set(fixture.name.clone()),
+        contact_details: Set(fixture.contact_details.clone()),
+        bakery_id: Set(bakery_id),
+      }
+      .insert(transaction)
+      .await?;
+    }
+
+    for statement in sequence_statements(&[bakery::Entity.table_name(), chef::Entity.table_name()]) {
+      transaction.execute_unprepared(&statement).await?;
+    }
+    info!("Loaded {} bakeries and {} chefs", self.bakery.len(), self.chef.len());
+
+    Ok(bakeries)
+  }
+}
+
+/// SQL statements moving "id" sequence of every table past the highest id
+pub fn sequence_statements(tables: &[&str]) -> Vec<String> {
+  tables
+    .iter()
+    .map(|table| {
+      format!(
+        "SELECT setval(pg_get_serial_sequence('{}', 'id'), COALESCE(MAX(id), 1), MAX(id) IS NOT NULL) FROM {}",
+        quote_ident(table).replace('\'', "''"),
+        quote_ident(table)
+      )
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod fixture_test {
+  use super::{sequence_statements, Fixtures};
+  use crate::entities::bakery;
+  use futures::executor::block_on;
+  use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
+  use serde_json::json;
+
+  #[test]
+  fn from_yaml() {
+    let fixtures = Fixtures::from_yaml(
+      "bakery:\n  - ref: happy\n    id: 10\n    name: Happy Bakery\n    profit_margin: 0.5\nchef:\n  - name: Jolie\n    \
+       contact_details:\n      email: jolie@example.com\n    bakery: happy\n",
+    )
+    .unwrap();
+
+    assert_eq!(fixtures.bakery[0].reference, Some(String::from("happy")));
+    assert_eq!(fixtures.bakery[0].id, Some(10));
+    assert_eq!(fixtures.chef[0].contact_details, Some(json!({ "email": "jolie@example.com" })));
+    assert_eq!(fixtures.chef[0].bakery, "happy");
+  }
+
+  #[test]
+  fn from_json_without_chefs() {
+    let fixtures = Fixtures::from_json(r#"{"bakery": [{"name": "Happy Bakery", "profit_margin": 0.0}]}"#).unwrap();
+
+    assert_eq!(fixtures.bakery.len(), 1);
+    assert!(fixtures.chef.is_empty());
+  }
+
+  #[test]
+  fn from_demo_file() {
+    let fixtures = Fixtures::from_file("fixtures/demo.yaml").unwrap();
+
+    assert_eq!(fixtures.bakery.len(), 2);
+    assert!(fixtures
+      .chef
+      .iter()
+      .all(|chef| fixtures.bakery.iter().any(|b| b.reference.as_ref() == Some(&chef.bakery))));
+  }
+
+  #[test]
+  fn sequence_reset() {
+    assert_eq!(
+      sequence_statements(&["chef"]),
+      vec![String::from(
+        "SELECT setval(pg_get_serial_sequence('\"chef\"', 'id'), COALESCE(MAX(id), 1), MAX(id) IS NOT NULL) FROM \"chef\""
+      )]
+    );
+  }
+
+  #[test]
+  fn load_with_unknown_reference() {
+    let connection = MockDatabase::new(DatabaseBackend::Postgres)
+      .append_query_results([[bakery::Model { id: 1, name: String::from("Happy Bakery"), profit_margin: 0.0 }]])
+      .append_exec_results([MockExecResult::default()])
+      .into_connection();
+    let fixtures = Fixtures::from_yaml(
+      "bakery:\n  - ref: happy\n    name: Happy Bakery\n    profit_margin: 0.0\nchef:\n  - name: Jolie\n    bakery: sad\n",
+    )
+    .unwrap();
+
+    match block_on(fixtures.load(&connection)) {
+      Err(e) => assert!(e.to_string().contains("Unknown bakery reference sad of chef Jolie")),
+      Ok(_) => panic!(),
+    }
+  }
+}
@@ -1,2 +1,10 @@
 pub mod connector;
+#[cfg(feature = "entities")]
+pub mod entities;
+#[cfg(feature = "entities")]
+pub mod fixture;
 pub mod migrator;
+pub mod profile;
+#[cfg(feature = "entities")]
+pub mod seeder;
+pub mod shutdown;
@@ -9,14 +9,15 @@ use sea_orm_migration::prelude::*;
 use rust_orm_template::connector::db::Db;
 
 async fn run() -> Result<(), DbErr> {
-  let db = Db::new().await.unwrap().connection;
-
-  let schema_manager = SchemaManager::new(&db);
+  let db = Db::new().await.unwrap();
+  let transaction = db.begin().await?;
+  let schema_manager = SchemaManager::new(&transaction);
 
   assert!(schema_manager.has_table("bakery").await?);
   assert!(schema_manager.has_table("chef").await?);
+  transaction.commit().await?;
 
-  Ok(())
+  db.close().await
 }
 
 fn main() {
@@ -1,16 +1,33 @@
 use sea_orm_migration::prelude::*;
 
+use crate::connector::db::{interleave, TransactionMode};
+
 mod m20220602_000001_create_bakery_table;
 mod m20220602_000002_create_chef_table;
 
+/// Names of migrations squashed into the first migration. DB which ran them is marked as having run it
+pub const SQUASHED: &[&str] = &[];
+
+/// Transaction modes of migrations not run in batch transaction, see `TransactionMode`.
+/// Modes of SQL migrations are declared by "-- transaction: <mode>" header of their up file instead
+pub const TRANSACTION_MODES: &[(&str, TransactionMode)] = &[];
+
+/// Plain SQL migrations of `sql` directory as (name, up, down), embedded by build script
+pub const SQL_MIGRATIONS: &[(&str, &str, Option<&str>)] = include!(concat!(env!("OUT_DIR"), "/sql_migrations.rs"));
+
 pub struct Migrator;
 
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
   fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-    vec![
-      Box::new(m20220602_000001_create_bakery_table::Migration),
-      Box::new(m20220602_000002_create_chef_table::Migration),
-    ]
+    interleave(rust_migrations(), SQL_MIGRATIONS)
   }
 }
+
+/// Migrations written in Rust. New ones are added at the end by `new_migration`
+fn rust_migrations() -> Vec<Box<dyn MigrationTrait>> {
+  vec![
+    Box::new(m20220602_000001_create_bakery_table::Migration),
+    Box::new(m20220602_000002_create_chef_table::Migration),
+  ]
+}
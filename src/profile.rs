@@ -0,0 +1,66 @@
+use std::env;
+
+/// Uses to describe environment the application runs in. Read from APP_PROFILE ENV, e.g. "development",
+/// "staging" or "production" (default value is "development"). Initialize by running:
+/// ```
+/// use rust_orm_template::profile::Profile;
+///
+/// let profile = Profile::from_env();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+  name: String,
+}
+
+impl Profile {
+  /// Uses to initialize profile by name. Name is case-insensitive
+  pub fn new(name: &str) -> Profile {
+    Profile { name: name.trim().to_lowercase() }
+  }
+
+  /// Uses to read profile from APP_PROFILE ENV
+  pub fn from_env() -> Profile {
+    Profile::new(&env::var("APP_PROFILE").unwrap_or(String::from("development")))
+  }
+
+  /// Profile name in lowercase
+  pub fn name(&self) -> &str {
+    &self.name
+  }
+
+  /// Returns true for "production" and "prod" profiles
+  pub fn is_production(&self) -> bool {
+    self.name == "production" || self.name == "prod"
+  }
+}
+
+impl Default for Profile {
+  fn default() -> Self {
+    Profile::new("development")
+  }
+}
+
+#[cfg(test)]
+mod profile_test {
+  use super::Profile;
+  use temp_env::with_var;
+
+  #[test]
+  fn from_env_without_profile() {
+    let profile = with_var("APP_PROFILE", None::<&str>, Profile::from_env);
+    assert_eq!(profile.name(), "development");
+    assert!(!profile.is_production());
+  }
+
+  #[test]
+  fn from_env_production() {
+    let profile = with_var("APP_PROFILE", Some("Production"), Profile::from_env);
+    assert!(profile.is_production());
+  }
+
+  #[test]
+  fn prod_is_production() {
+    assert!(Profile::new("prod").is_production());
+    assert!(!Profile::new("staging").is_production());
+  }
+}
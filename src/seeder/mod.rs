@@ -0,0 +1,11 @@
+use crate::connector::db::Seeder;
+
+mod s20220602_000001_seed_bakeries;
+
+pub struct Seeds;
+
+impl Seeds {
+  pub fn seeders() -> Vec<Box<dyn Seeder>> {
+    vec![Box::new(s20220602_000001_seed_bakeries::Seed)]
+  }
+}
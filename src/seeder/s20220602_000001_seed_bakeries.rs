@@ -0,0 +1,41 @@
+use sea_orm::prelude::*;
+use sea_orm::{DatabaseTransaction, Set};
+use serde_json::json;
+
+use crate::connector::db::Seeder;
+use crate::entities::{bakery, chef};
+
+pub struct Seed;
+
+#[async_trait::async_trait]
+impl Seeder for Seed {
+  fn name(&self) -> &str {
+    "s_20220602_000001_seed_bakeries"
+  }
+
+  // Demo data is not needed on production
+  fn profiles(&self) -> Vec<&str> {
+    vec!["development", "staging"]
+  }
+
+  // Define data to insert: a bakery with its chefs.
+  async fn run(&self, connection: &DatabaseTransaction) -> Result<(), DbErr> {
+    let bakery =
+      bakery::ActiveModel { name: Set(String::from("Happy Bakery")), profit_margin: Set(0.0), ..Default::default() }
+        .insert(connection)
+        .await?;
+
+    for (name, email) in [("Jolie", "jolie@example.com"), ("Charles", "charles@example.com")] {
+      chef::ActiveModel {
+        name: Set(String::from(name)),
+        contact_details: Set(Some(json!({ "email": email }))),
+        bakery_id: Set(bakery.id),
+        ..Default::default()
+      }
+      .insert(connection)
+      .await?;
+    }
+
+    Ok(())
+  }
+}
@@ -0,0 +1,63 @@
+use std::io::Error;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use signal_hook::consts::TERM_SIGNALS;
+use signal_hook::flag;
+
+/// Uses to defer termination signals until current work is finished. The first SIGTERM/SIGINT only marks
+/// shutdown as requested, the second one terminates process immediately. Initialize by running:
+/// ```
+/// use rust_orm_template::shutdown::Shutdown;
+///
+/// let shutdown = Shutdown::install().unwrap();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Shutdown {
+  requested: Arc<AtomicBool>,
+}
+
+impl Shutdown {
+  /// Uses to initialize shutdown flag without signal handling
+  pub fn new() -> Shutdown {
+    Shutdown::default()
+  }
+
+  /// Uses to initialize shutdown flag raised by termination signals
+  pub fn install() -> Result<Shutdown, Error> {
+    let shutdown = Shutdown::new();
+    for signal in TERM_SIGNALS {
+      flag::register_conditional_shutdown(*signal, 1, Arc::clone(&shutdown.requested))?;
+      flag::register(*signal, Arc::clone(&shutdown.requested))?;
+    }
+    Ok(shutdown)
+  }
+
+  /// Mark shutdown as requested
+  pub fn request(&self) {
+    self.requested.store(true, Ordering::SeqCst);
+  }
+
+  /// Returns true if shutdown was requested
+  pub fn is_requested(&self) -> bool {
+    self.requested.load(Ordering::SeqCst)
+  }
+}
+
+#[cfg(test)]
+mod shutdown_test {
+  use super::Shutdown;
+
+  #[test]
+  fn new_is_not_requested() {
+    assert!(!Shutdown::new().is_requested());
+  }
+
+  #[test]
+  fn request_is_shared_between_clones() {
+    let shutdown = Shutdown::new();
+    let clone = shutdown.clone();
+    clone.request();
+    assert!(shutdown.is_requested());
+  }
+}